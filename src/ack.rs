@@ -0,0 +1,36 @@
+use clash_sh::WorktreeManager;
+use clash_sh::registry::{Ack, Registry};
+use colored::Colorize;
+
+/// Acknowledge a conflict on `path` between two worktrees.
+///
+/// The conflict is hidden from `status`, `check` and `watch` until either
+/// worktree's HEAD blob for the path changes. Worktrees may be given by id
+/// or branch name.
+pub fn run_ack(
+    worktrees: &WorktreeManager,
+    wt1: &str,
+    wt2: &str,
+    path: &str,
+) -> Result<(), String> {
+    let a = worktrees.find(wt1).map_err(|e| e.to_string())?;
+    let b = worktrees.find(wt2).map_err(|e| e.to_string())?;
+    if a.id == b.id {
+        return Err(format!("cannot ack a worktree against itself ('{}')", a.id));
+    }
+
+    let path = path.trim_start_matches("./");
+    let ack = Ack::new(a, b, path);
+    Registry::open(worktrees)
+        .add_ack(ack)
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "{} {} between {} and {} (until either side changes it)",
+        "Acknowledged".bright_green().bold(),
+        path.yellow(),
+        a.id.bright_blue(),
+        b.id.bright_blue()
+    );
+    Ok(())
+}
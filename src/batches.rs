@@ -0,0 +1,304 @@
+//! `clash batches`: which worktrees can land together safely
+//!
+//! Worktrees that conflict pairwise can't be merged in the same batch. The
+//! fewest batches without a conflicting pair is a minimum coloring of the
+//! conflict graph: found exactly for the usual handful of worktrees, and
+//! greedily (DSatur) beyond [`EXACT_LIMIT`]. No pairwise conflict doesn't
+//! guarantee a clean merge of the whole batch, so each batch is then merged
+//! in sequence on top of the main worktree to verify it.
+
+use crate::status::check_pairs;
+use clash_sh::output::{Batch, BatchesOutput};
+use clash_sh::{Suppressions, Worktree, WorktreeManager};
+use colored::Colorize;
+use std::collections::HashMap;
+
+/// Largest number of worktrees colored exactly; more are colored greedily
+const EXACT_LIMIT: usize = 20;
+
+/// Partition the worktrees (other than the main one) into the fewest
+/// batches without a conflicting pair, and verify each batch.
+///
+/// Pairs that couldn't be checked are treated as conflicting. Ignored and
+/// acknowledged conflicts don't separate worktrees unless `all` is set.
+///
+/// Returns exit code 2 if any batch fails verification.
+pub fn run_batches(worktrees: &WorktreeManager, all: bool, json: bool) -> Result<i32, String> {
+    let output = plan_batches(worktrees, all);
+    let failed = output.batches.iter().any(|b| !b.verified);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?
+        );
+    } else {
+        display(worktrees, &output);
+    }
+    Ok(if failed { 2 } else { 0 })
+}
+
+/// Color the conflict graph into batches and merge each batch in sequence
+fn plan_batches(worktrees: &WorktreeManager, all: bool) -> BatchesOutput {
+    let target = worktrees.main();
+    let candidates: Vec<&Worktree> = worktrees
+        .iter()
+        .filter(|wt| target.is_none_or(|main| main.id != wt.id))
+        .collect();
+    let index: HashMap<&str, usize> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, wt)| (wt.id.as_str(), i))
+        .collect();
+
+    let (pairs, _) = check_pairs(worktrees, all);
+    let suppressions = if all {
+        Suppressions::none()
+    } else {
+        Suppressions::load(worktrees)
+    };
+    let mut adjacent = vec![vec![false; candidates.len()]; candidates.len()];
+    for pair in pairs
+        .iter()
+        .filter(|p| p.error.is_some() || !p.conflicting_files.is_empty())
+    {
+        if let (Some(&a), Some(&b)) = (
+            index.get(pair.wt1.id.as_str()),
+            index.get(pair.wt2.id.as_str()),
+        ) {
+            adjacent[a][b] = true;
+            adjacent[b][a] = true;
+        }
+    }
+
+    let mut batches = Vec::new();
+    for group in color_classes(&min_coloring(&adjacent)) {
+        let mut members: Vec<&Worktree> = target.into_iter().collect();
+        members.extend(group.iter().map(|&i| candidates[i]));
+        let (conflict, error) = match worktrees.merge_in_sequence(&members, &suppressions) {
+            Ok(conflict) => (conflict, None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        batches.push(Batch {
+            worktrees: group.iter().map(|&i| candidates[i].id.clone()).collect(),
+            verified: conflict.is_none() && error.is_none(),
+            conflict,
+            error,
+        });
+    }
+    BatchesOutput::new(target.map(|main| main.id.clone()), batches)
+}
+
+fn display(worktrees: &WorktreeManager, output: &BatchesOutput) {
+    if output.batches.is_empty() {
+        println!("No worktrees to batch besides the main worktree");
+        return;
+    }
+    let name = |id: &str| match worktrees.find(id) {
+        Ok(wt) => format!("{} [{}]", wt.id.bright_blue(), wt.branch.bright_magenta()),
+        Err(_) => id.bright_blue().to_string(),
+    };
+
+    let onto = output
+        .target
+        .as_deref()
+        .map(|t| format!(" onto {}", name(t)))
+        .unwrap_or_default();
+    println!(
+        "{}",
+        format!(
+            "{} batch{} that can land together{}:",
+            output.batches.len(),
+            if output.batches.len() == 1 { "" } else { "es" },
+            onto
+        )
+        .bright_cyan()
+        .bold()
+    );
+    for (i, batch) in output.batches.iter().enumerate() {
+        let members: Vec<String> = batch.worktrees.iter().map(|id| name(id)).collect();
+        println!(
+            "\n  {} {}",
+            format!("Batch {}:", i + 1).bold(),
+            members.join(", ")
+        );
+        match (&batch.conflict, &batch.error) {
+            (Some(conflict), _) => {
+                let after: Vec<String> = conflict.merged.iter().map(|id| name(id)).collect();
+                println!(
+                    "    {} {} doesn't merge after {}: {}",
+                    "✗".bright_red().bold(),
+                    name(&conflict.worktree),
+                    after.join(", "),
+                    conflict.files.join(", ").yellow()
+                );
+            }
+            (None, Some(error)) => {
+                println!("    {} could not verify: {}", "?".bright_yellow(), error);
+            }
+            (None, None) => println!("    {} merges cleanly", "✓".bright_green().bold()),
+        }
+    }
+}
+
+/// Nodes grouped by color, largest group first (ties keep color order)
+fn color_classes(colors: &[usize]) -> Vec<Vec<usize>> {
+    let count = colors.iter().max().map_or(0, |&c| c + 1);
+    let mut classes = vec![Vec::new(); count];
+    for (node, &color) in colors.iter().enumerate() {
+        classes[color].push(node);
+    }
+    classes.sort_by_key(|class| std::cmp::Reverse(class.len()));
+    classes
+}
+
+/// Color the nodes so no two adjacent nodes share a color, with as few
+/// colors as possible (exactly up to [`EXACT_LIMIT`] nodes)
+fn min_coloring(adjacent: &[Vec<bool>]) -> Vec<usize> {
+    let greedy = dsatur(adjacent);
+    let greedy_count = greedy.iter().max().map_or(0, |&c| c + 1);
+    if adjacent.len() > EXACT_LIMIT {
+        return greedy;
+    }
+    (1..greedy_count)
+        .find_map(|k| color_with(adjacent, k))
+        .unwrap_or(greedy)
+}
+
+/// Greedy coloring, always picking the node with the most differently
+/// colored neighbours next (DSatur)
+fn dsatur(adjacent: &[Vec<bool>]) -> Vec<usize> {
+    let n = adjacent.len();
+    let mut colors: Vec<Option<usize>> = vec![None; n];
+    for _ in 0..n {
+        let saturation = |node: usize| {
+            let mut seen: Vec<usize> = (0..n)
+                .filter(|&m| adjacent[node][m])
+                .filter_map(|m| colors[m])
+                .collect();
+            seen.sort_unstable();
+            seen.dedup();
+            seen.len()
+        };
+        let degree = |node: usize| adjacent[node].iter().filter(|&&a| a).count();
+        let Some(node) = (0..n)
+            .filter(|&node| colors[node].is_none())
+            .max_by_key(|&node| (saturation(node), degree(node), std::cmp::Reverse(node)))
+        else {
+            break;
+        };
+        let color = (0..)
+            .find(|&c| (0..n).all(|m| !adjacent[node][m] || colors[m] != Some(c)))
+            .unwrap_or(0);
+        colors[node] = Some(color);
+    }
+    colors.into_iter().map(|c| c.unwrap_or(0)).collect()
+}
+
+/// Color with at most `k` colors by backtracking, if possible
+fn color_with(adjacent: &[Vec<bool>], k: usize) -> Option<Vec<usize>> {
+    fn assign(
+        adjacent: &[Vec<bool>],
+        order: &[usize],
+        k: usize,
+        colors: &mut [Option<usize>],
+        used: usize,
+    ) -> bool {
+        let Some((&node, rest)) = order.split_first() else {
+            return true;
+        };
+        // A fresh color is interchangeable with any other fresh one
+        for color in 0..k.min(used + 1) {
+            let free = (0..adjacent.len()).all(|m| !adjacent[node][m] || colors[m] != Some(color));
+            if free {
+                colors[node] = Some(color);
+                if assign(adjacent, rest, k, colors, used.max(color + 1)) {
+                    return true;
+                }
+                colors[node] = None;
+            }
+        }
+        false
+    }
+
+    // Most constrained nodes first prunes the search early
+    let mut order: Vec<usize> = (0..adjacent.len()).collect();
+    order.sort_by_key(|&node| std::cmp::Reverse(adjacent[node].iter().filter(|&&a| a).count()));
+    let mut colors = vec![None; adjacent.len()];
+    assign(adjacent, &order, k, &mut colors, 0)
+        .then(|| colors.into_iter().map(|c| c.unwrap_or(0)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+    use clash_sh::registry::{Ack, Registry};
+
+    fn graph(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<bool>> {
+        let mut adjacent = vec![vec![false; n]; n];
+        for &(a, b) in edges {
+            adjacent[a][b] = true;
+            adjacent[b][a] = true;
+        }
+        adjacent
+    }
+
+    #[test]
+    fn coloring_is_minimal_and_proper() {
+        // A 5-cycle needs 3 colors; a bipartite graph (crown) needs 2 even
+        // where greedy orderings can use more
+        let cases = [
+            (graph(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]), 3),
+            (
+                graph(
+                    8,
+                    &[
+                        (0, 5),
+                        (0, 7),
+                        (2, 1),
+                        (2, 7),
+                        (4, 1),
+                        (4, 3),
+                        (6, 1),
+                        (6, 3),
+                        (6, 5),
+                    ],
+                ),
+                2,
+            ),
+            (graph(3, &[]), 1),
+        ];
+        for (adjacent, expected) in cases {
+            let colors = min_coloring(&adjacent);
+            assert_eq!(colors.iter().max().unwrap() + 1, expected);
+            for a in 0..adjacent.len() {
+                for b in 0..adjacent.len() {
+                    assert!(!adjacent[a][b] || colors[a] != colors[b]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn acked_conflicts_neither_separate_nor_fail_batches() {
+        let repo = TestRepo::new(&[("a.txt", "base\n")]);
+        repo.add_worktree("feature");
+        for (wt, content) in [("main", "ours\n"), ("feature", "theirs\n")] {
+            repo.write(wt, "a.txt", content);
+            repo.commit(wt, "change");
+        }
+        let worktrees = repo.manager();
+        let output = plan_batches(&worktrees, false);
+        assert!(!output.batches[0].verified);
+
+        let (main, feature) = (&worktrees.all()[0], &worktrees.all()[1]);
+        Registry::open(&worktrees)
+            .add_ack(Ack::new(main, feature, "a.txt"))
+            .unwrap();
+        let output = plan_batches(&worktrees, false);
+        assert_eq!(output.batches.len(), 1);
+        assert!(output.batches[0].verified);
+        assert!(!plan_batches(&worktrees, true).batches[0].verified);
+    }
+}
@@ -0,0 +1,182 @@
+//! Cross-worktree briefing for agents starting work
+//!
+//! `clash brief` summarizes where the other worktrees are working: which
+//! ones have changes, which files they touched, and which files already
+//! conflict with the current worktree. With `--hook` it reads a
+//! SessionStart or UserPromptSubmit hook payload and answers with the
+//! summary as additional context, so every agent starts a task knowing
+//! where the others are.
+
+use crate::status::check_pairs;
+use clash_sh::{Worktree, WorktreeManager};
+use serde_json::json;
+use std::fmt::Write;
+use std::io::{IsTerminal, Read};
+
+/// Changed files listed per worktree before the rest are summarized
+const FILE_LIMIT: usize = 10;
+
+/// Hook event the briefing answers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BriefHook {
+    /// Once when an agent session starts
+    SessionStart,
+    /// Before each prompt the user submits
+    PromptSubmit,
+}
+
+impl BriefHook {
+    fn event_name(self) -> &'static str {
+        match self {
+            BriefHook::SessionStart => "SessionStart",
+            BriefHook::PromptSubmit => "UserPromptSubmit",
+        }
+    }
+}
+
+/// Print the briefing for the current worktree, or answer a hook with it.
+///
+/// In hook mode the worktree is taken from the payload's `cwd`, and nothing
+/// is printed when there are no other worktrees to report on.
+pub fn run_brief(hook: Option<BriefHook>, all: bool) -> Result<(), String> {
+    let cwd = match hook {
+        Some(_) => read_payload_cwd()?,
+        None => None,
+    };
+    let start = cwd.as_deref().unwrap_or(".");
+    let worktrees = WorktreeManager::discover_from(start).map_err(|e| e.to_string())?;
+    let current = cwd
+        .as_deref()
+        .and_then(|dir| {
+            let dir = std::path::Path::new(dir);
+            worktrees.find_containing(&dir.canonicalize().unwrap_or(dir.to_path_buf()))
+        })
+        .or_else(|| worktrees.current())
+        .ok_or("current directory is not inside any known worktree")?;
+
+    let briefing = build_briefing(&worktrees, current, all);
+    match hook {
+        None => println!(
+            "{}",
+            briefing.unwrap_or_else(|| "No other worktrees".into())
+        ),
+        Some(event) => {
+            if let Some(briefing) = briefing {
+                let output = json!({
+                    "hookSpecificOutput": {
+                        "hookEventName": event.event_name(),
+                        "additionalContext": briefing,
+                    }
+                });
+                println!("{}", output);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Summarize the other worktrees' changes and their conflicts with `current`.
+///
+/// Returns `None` when `current` is the only worktree.
+fn build_briefing(worktrees: &WorktreeManager, current: &Worktree, all: bool) -> Option<String> {
+    let others: Vec<&Worktree> = worktrees.iter().filter(|w| w.id != current.id).collect();
+    if others.is_empty() {
+        return None;
+    }
+
+    let mut text = format!(
+        "Clash briefing for worktree {} [{}]:\n",
+        current.id, current.branch
+    );
+
+    let active: Vec<(&Worktree, Vec<String>)> = others
+        .iter()
+        .filter_map(|other| {
+            let files = other.changed_files(current).ok()?;
+            (!files.is_empty()).then_some((*other, files))
+        })
+        .collect();
+    if active.is_empty() {
+        text.push_str("No other worktree has changes.\n");
+    } else {
+        text.push_str("Other worktrees with changes:\n");
+        for (other, files) in &active {
+            let _ = write!(
+                text,
+                "- {} [{}]: {}",
+                other.id,
+                other.branch,
+                files
+                    .iter()
+                    .take(FILE_LIMIT)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            if files.len() > FILE_LIMIT {
+                let _ = write!(text, " and {} more", files.len() - FILE_LIMIT);
+            }
+            text.push('\n');
+        }
+    }
+    let idle = others.len() - active.len();
+    if idle > 0 && !active.is_empty() {
+        let _ = writeln!(
+            text,
+            "{} other worktree{} without changes.",
+            idle,
+            if idle == 1 { "" } else { "s" }
+        );
+    }
+
+    let (pairs, _) = check_pairs(worktrees, all);
+    let conflicts: Vec<_> = pairs
+        .iter()
+        .filter(|p| !p.conflicting_files.is_empty())
+        .filter_map(|p| {
+            if p.wt1.id == current.id {
+                Some((&p.wt2, &p.conflicting_files))
+            } else if p.wt2.id == current.id {
+                Some((&p.wt1, &p.conflicting_files))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if conflicts.is_empty() {
+        text.push_str("No conflicts with this worktree.\n");
+    } else {
+        text.push_str("Files that conflict with this worktree (coordinate before editing):\n");
+        for (other, files) in conflicts {
+            let _ = writeln!(
+                text,
+                "- {} [{}]: {}",
+                other.id,
+                other.branch,
+                files.join(", ")
+            );
+        }
+    }
+
+    Some(text.trim_end().to_string())
+}
+
+/// Read the hook payload from stdin and return its `cwd`, if any.
+///
+/// A missing or empty payload falls back to the current directory.
+fn read_payload_cwd() -> Result<Option<String>, String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+    let mut input = String::new();
+    stdin
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Failed to read hook input: {}", e))?;
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    let payload: serde_json::Value =
+        serde_json::from_str(&input).map_err(|e| format!("Invalid hook input: {}", e))?;
+    Ok(payload["cwd"].as_str().map(str::to_string))
+}
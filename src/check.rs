@@ -1,46 +1,42 @@
-use clash_sh::{Worktree, WorktreeManager};
-use serde::Serialize;
+mod adapter;
+mod annotate;
+mod context;
+mod edit;
+mod patch;
+mod policy;
+mod shell;
+
+pub use adapter::Agent;
+
+use crate::format::{self, OutputFormat};
+use adapter::{HookInput, HookResponse};
+use clash_sh::config::{Config, ErrorDecision, Policy};
+use clash_sh::intent;
+use clash_sh::output::{
+    CheckOutput, ClaimInfo, Decision, FileConflict, PatchCheckOutput, SCHEMA_VERSION,
+};
+use clash_sh::registry::{EditPhase, LedgerEntry, Registry};
+use clash_sh::{
+    Side, Suppressions, Worktree, WorktreeError, WorktreeManager, WorktreePairConflict,
+};
+use edit::LineRange;
+use serde::{Deserialize, Serialize};
 use std::io::{IsTerminal, Read};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 // ============================================================================
-// Output types
+// Proposed edits
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-struct CheckOutput {
-    file: String,
-    current_worktree: String,
-    current_branch: String,
-    conflicts: Vec<FileConflict>,
-}
-
-#[derive(Debug, Serialize)]
-struct FileConflict {
-    worktree: String,
-    branch: String,
-    has_merge_conflict: bool,
-    has_active_changes: bool,
-}
-
-/// Claude Code hook JSON output format.
-///
-/// When output on stdout with exit 0, Claude Code interprets
-/// `permissionDecision` to decide whether to allow, deny, or ask.
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct HookOutput {
-    hook_specific_output: HookDecision,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct HookDecision {
-    hook_event_name: &'static str,
-    permission_decision: &'static str,
-    permission_decision_reason: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    additional_context: Option<String>,
+/// An edit the caller is about to make to the checked file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Proposal {
+    /// The file's full content after the edit
+    pub content: String,
+    /// Lines of the current file the edit replaces, when known
+    #[serde(default)]
+    pub lines: Option<Vec<LineRange>>,
 }
 
 // ============================================================================
@@ -63,6 +59,10 @@ pub enum CheckError {
     ConflictDetection { worktree: String, reason: String },
     /// Failed to read or parse hook input from stdin
     HookInput(String),
+    /// Failed to read a patch file
+    PatchInput(String),
+    /// `.clash.toml` is unreadable or invalid
+    Config(String),
 }
 
 impl std::fmt::Display for CheckError {
@@ -87,6 +87,8 @@ impl std::fmt::Display for CheckError {
                 )
             }
             Self::HookInput(msg) => write!(f, "hook input error: {}", msg),
+            Self::PatchInput(msg) => write!(f, "patch input error: {}", msg),
+            Self::Config(msg) => write!(f, "{}", msg),
         }
     }
 }
@@ -97,108 +99,447 @@ impl std::fmt::Display for CheckError {
 
 /// Check a single file for conflicts across worktrees.
 ///
-/// - `Some(path)` — manual mode: JSON to stdout, exit 2 if conflicts
-/// - `None` — hook mode: reads file path from stdin, hook decision JSON to stdout
+/// - `Some(path)` — manual mode: results to stdout in `format`, exit 2 if
+///   conflicts
+/// - `None` — hook mode: reads the agent's hook payload from stdin and
+///   answers in that agent's format (see [`Agent`]); `hook` selects the
+///   agent, otherwise it is detected from the payload. For shell tool
+///   payloads every file the command writes, moves or deletes is checked,
+///   and the strictest decision wins
+///
+/// Paths claimed by another worktree (via `clash claim`) count as conflicts,
+/// and in hook mode they produce a "deny" decision instead of "ask".
 ///
 /// Discovers worktrees from the file's location, so it works regardless
-/// of the current working directory.
+/// of the current working directory. Ignored and acknowledged conflicts
+/// are skipped unless `all` is set.
 ///
-/// Returns whether conflicts were found:
-/// - `Ok(false)` — no conflicts
-/// - `Ok(true)` — conflicts found
+/// Returns the exit code:
+/// - `Ok(0)` — no conflicts, or the agent was told about them on stdout
+/// - `Ok(2)` — conflicts found (manual mode), or the edit must be blocked
+///   (agents that use the exit code to deny)
 /// - `Err(e)` — operational error, caller prints to stderr and exits 1
-pub fn run_check(path: Option<&str>) -> Result<bool, CheckError> {
-    let (file_path, hook_mode) = match path {
-        Some(p) => (p.to_string(), false),
-        None => (read_hook_input()?, true),
+pub fn run_check(
+    path: Option<&str>,
+    all: bool,
+    hook: Option<Agent>,
+    format: OutputFormat,
+) -> Result<i32, CheckError> {
+    let Some(path) = path else {
+        return run_hook(all, hook);
     };
 
-    let worktrees = WorktreeManager::discover_from(&file_path)
-        .map_err(|e| CheckError::HookInput(format!("cannot discover worktrees: {}", e)))?;
-    run_check_inner(&worktrees, &file_path, hook_mode)
+    let output = check_path(path, None, all, None)?;
+    let has_conflicts = output.has_conflicts();
+    let worktrees = WorktreeManager::discover_from(path).ok();
+    print_results(
+        format,
+        worktrees.as_ref(),
+        &output,
+        std::slice::from_ref(&output),
+    );
+    Ok(if has_conflicts { 2 } else { 0 })
+}
+
+/// Hook mode: check every file the agent's tool will modify and answer in
+/// the agent's format, with the decision taken by the repository's policy
+fn run_hook(all: bool, hook: Option<Agent>) -> Result<i32, CheckError> {
+    let payload = read_hook_payload()?;
+    let agent = hook.unwrap_or_else(|| Agent::detect(&payload));
+    let response = hook_response(agent, &payload, all)?;
+    let exit_code = response.exit_code;
+    print_hook_response(response);
+    Ok(exit_code)
 }
 
-fn run_check_inner(
+/// Decide on a hook payload and render the agent's response.
+///
+/// Files outside every worktree are none of clash's business and pass.
+/// When `.clash.toml` can't be read there's no telling what `on_error`
+/// asked for, so every edit is asked about with the error as the reason.
+fn hook_response(
+    agent: Agent,
+    payload: &serde_json::Value,
+    all: bool,
+) -> Result<HookResponse, CheckError> {
+    // The policy lives in the repository the agent works in
+    let start = payload["cwd"].as_str().unwrap_or(".");
+    let worktrees = WorktreeManager::discover_from(start).ok();
+
+    // After the edit there's nothing to decide, only the ledger to update
+    if agent.is_post_edit(payload) {
+        if let Some(worktrees) = &worktrees {
+            record_completed(agent, payload, worktrees)?;
+        }
+        return Ok(HookResponse::default());
+    }
+
+    let config = match &worktrees {
+        Some(worktrees) => Config::load(worktrees).map_err(|e| CheckError::Config(e.to_string())),
+        None => Ok(Config::default()),
+    };
+    let (policy, input) = match config {
+        Ok(config) => (config.policy, agent.parse(payload)),
+        Err(e) => (
+            Policy {
+                on_error: ErrorDecision::Ask,
+                ..Policy::default()
+            },
+            Err(e),
+        ),
+    };
+    let session = input.as_ref().ok().and_then(|i| i.session_id.clone());
+    let (decision, reason, outputs) = match input.and_then(|input| hook_outputs(&input, all)) {
+        Ok(mut outputs) => {
+            let decisions: Vec<Decision> = outputs
+                .iter_mut()
+                .map(|output| policy::apply(&policy, worktrees.as_ref(), output))
+                .collect();
+            if let Some(worktrees) = &worktrees {
+                let tool = agent.tool_name(payload);
+                let entries = outputs
+                    .iter()
+                    .zip(&decisions)
+                    .map(|(output, decision)| LedgerEntry {
+                        session: session.clone(),
+                        tool: tool.clone(),
+                        decision: Some(decision.as_str().to_string()),
+                        ..LedgerEntry::new(
+                            &output.current_worktree,
+                            &output.file,
+                            EditPhase::Attempted,
+                        )
+                    })
+                    .collect();
+                record_edits(worktrees, entries);
+            }
+
+            let decision = decisions.into_iter().max().unwrap_or(Decision::Allow);
+            let reason = outputs
+                .iter()
+                .filter(|o| {
+                    o.has_conflicts()
+                        || !o.distant_conflicts.is_empty()
+                        || !o.recent_edits.is_empty()
+                })
+                .map(format_conflict_reason)
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            (decision, reason, outputs)
+        }
+        Err(e) => {
+            let decision = match policy.on_error {
+                ErrorDecision::Allow => return Err(e),
+                ErrorDecision::Ask => Decision::Ask,
+                ErrorDecision::Deny => Decision::Deny,
+            };
+            (decision, format!("clash check failed: {}", e), Vec::new())
+        }
+    };
+
+    // What the other worktrees changed, so the agent can adapt its edit
+    let details = worktrees
+        .map(|worktrees| context::describe(&worktrees, &outputs))
+        .unwrap_or_default();
+    let context = [reason.clone(), format_messages(&outputs), details]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(agent.respond(decision, &reason, &context, &outputs))
+}
+
+/// Check every file named or touched by the hook payload
+fn hook_outputs(input: &HookInput, all: bool) -> Result<Vec<CheckOutput>, CheckError> {
+    let mut outputs = Vec::new();
+    for path in &input.file_paths {
+        let proposed = input.edit.as_ref().and_then(|edit| {
+            let current = std::fs::read_to_string(path).ok();
+            Some(Proposal {
+                content: edit.apply(current.as_deref())?,
+                lines: current.and_then(|c| edit.line_ranges(&c)),
+            })
+        });
+        match check_path(path, input.session_id.as_deref(), all, proposed.as_ref()) {
+            Ok(output) => outputs.push(output),
+            Err(CheckError::NotInWorktree(_)) => continue,
+            // Shell commands and patches routinely touch files outside the repository
+            Err(e) if input.extracted && !matches!(e, CheckError::ConflictDetection { .. }) => {
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(outputs)
+}
+
+/// Check every file touched by a unified diff or `apply_patch` patch.
+///
+/// `source` is a patch file, or `-` for stdin. Relative paths in the patch
+/// are resolved against the root of the current worktree, where `git diff`
+/// and `apply_patch` paths are rooted. Prints one result per file in
+/// `format` and returns exit code 2 if any file has conflicts.
+pub fn run_check_patch(source: &str, all: bool, format: OutputFormat) -> Result<i32, CheckError> {
+    let patch = if source == "-" {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf).map(|_| buf)
+    } else {
+        std::fs::read_to_string(source)
+    }
+    .map_err(|e| CheckError::PatchInput(format!("failed to read {}: {}", source, e)))?;
+
+    let cwd = std::env::current_dir()
+        .and_then(|d| d.canonicalize())
+        .map_err(CheckError::CurrentDir)?;
+    let worktrees = WorktreeManager::discover().ok();
+    let root = worktrees
+        .as_ref()
+        .and_then(|wts| wts.find_containing(&cwd).map(|wt| wt.path.clone()))
+        .unwrap_or(cwd);
+
+    let mut files = Vec::new();
+    for path in patch::touched_paths(&patch) {
+        let path = root.join(path).to_string_lossy().into_owned();
+        files.push(check_path(&path, None, all, None)?);
+    }
+
+    let has_conflicts = files.iter().any(CheckOutput::has_conflicts);
+    let output = PatchCheckOutput::new(files);
+    print_results(format, worktrees.as_ref(), &output, &output.files);
+    Ok(if has_conflicts { 2 } else { 0 })
+}
+
+/// Print check results: `document` as JSON, or `outputs` in another format
+fn print_results(
+    format: OutputFormat,
+    worktrees: Option<&WorktreeManager>,
+    document: &impl Serialize,
+    outputs: &[CheckOutput],
+) {
+    match format {
+        OutputFormat::Json => {
+            // Serialization of simple String/bool fields cannot fail in practice
+            let json = serde_json::to_string_pretty(document)
+                .expect("check output is always serializable");
+            println!("{}", json);
+        }
+        OutputFormat::Text => {
+            for output in outputs {
+                if output.has_conflicts() || !output.distant_conflicts.is_empty() {
+                    println!("{}", format_conflict_reason(output));
+                } else {
+                    println!("No conflicts on {}", output.file);
+                }
+            }
+        }
+        _ => {
+            let suites: Vec<String> = outputs.iter().map(|o| o.file.clone()).collect();
+            let annotations = annotate::annotations(worktrees, outputs);
+            println!("{}", format::render(format, &suites, &annotations));
+        }
+    }
+}
+
+/// Check a file, asking a running daemon first and computing directly otherwise
+fn check_path(
+    path: &str,
+    session: Option<&str>,
+    all: bool,
+    proposed: Option<&Proposal>,
+) -> Result<CheckOutput, CheckError> {
+    // A running daemon already has worktrees discovered and pairs merged
+    if let Some(output) = crate::daemon::check(path, session, all, proposed) {
+        return Ok(output);
+    }
+
+    let worktrees = WorktreeManager::discover_from(path).map_err(|e| match e {
+        WorktreeError::NotARepository { .. } => CheckError::NotInWorktree(PathBuf::from(path)),
+        e => CheckError::HookInput(format!("cannot discover worktrees: {}", e)),
+    })?;
+    let suppressions = if all {
+        Suppressions::none()
+    } else {
+        Suppressions::load(&worktrees)
+    };
+    check_file(&worktrees, path, session, &suppressions, None, proposed)
+}
+
+fn print_hook_response(response: HookResponse) {
+    if let Some(out) = response.stdout {
+        println!("{}", out);
+    }
+    if let Some(err) = response.stderr {
+        eprintln!("{}", err);
+    }
+}
+
+/// Check one file against every other worktree without printing anything.
+///
+/// Shared by the CLI, the MCP server and the daemon so all report identical
+/// results. When `cached_pairs` is given (by the daemon), merge conflicts are
+/// looked up there instead of being recomputed.
+///
+/// When `proposed` describes an edit the caller is about to make, the edit
+/// is merged in memory against every other worktree and `introduces_conflict`
+/// reports which merges it would break. If the edit's lines are known,
+/// conflicts whose changes are all far from them go to `distant_conflicts`.
+pub fn check_file(
     worktrees: &WorktreeManager,
     path: &str,
-    hook_mode: bool,
-) -> Result<bool, CheckError> {
+    session: Option<&str>,
+    suppressions: &Suppressions,
+    cached_pairs: Option<&[WorktreePairConflict]>,
+    proposed: Option<&Proposal>,
+) -> Result<CheckOutput, CheckError> {
     let (current_wt, repo_relative) = resolve_file_path(path, worktrees)?;
 
     let mut conflicts = Vec::new();
+    let mut distant_conflicts = Vec::new();
 
     for other_wt in worktrees.iter() {
-        if other_wt.id == current_wt.id {
+        if other_wt.id == current_wt.id
+            || suppressions.is_suppressed(current_wt, other_wt, &repo_relative)
+        {
             continue;
         }
 
-        let merge_conflicts =
-            current_wt
+        let merge_conflicts = match cached_pairs {
+            Some(pairs) => cached_conflicts(pairs, current_wt, other_wt),
+            None => current_wt
                 .conflicts_with(other_wt)
-                .map_err(|e| CheckError::ConflictDetection {
-                    worktree: other_wt.id.clone(),
-                    reason: e.to_string(),
-                })?;
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|reason| CheckError::ConflictDetection {
+            worktree: other_wt.id.clone(),
+            reason,
+        })?;
 
         let has_merge_conflict = merge_conflicts.iter().any(|f| f == &repo_relative);
-        let has_active_changes = file_has_active_changes(&other_wt.path, &repo_relative);
-
-        if has_merge_conflict || has_active_changes {
-            conflicts.push(FileConflict {
-                worktree: other_wt.id.clone(),
-                branch: other_wt.branch.clone(),
-                has_merge_conflict,
-                has_active_changes,
-            });
+        let has_active_changes = file_has_active_changes(other_wt, &repo_relative);
+        let introduces_conflict = match proposed {
+            Some(Proposal { content, .. }) => {
+                let detect = |content: Option<&[u8]>| {
+                    current_wt
+                        .file_conflicts_with(other_wt, &repo_relative, content)
+                        .map_err(|e| CheckError::ConflictDetection {
+                            worktree: other_wt.id.clone(),
+                            reason: e.to_string(),
+                        })
+                };
+                let after = detect(Some(content.as_bytes()))?;
+                // The file as it is now, uncommitted changes included, may
+                // conflict already; then the edit isn't what introduces it
+                let before = after && {
+                    let current = std::fs::read(current_wt.path.join(&repo_relative)).ok();
+                    detect(current.as_deref())?
+                };
+                Some(after && !before)
+            }
+            None => None,
+        };
+
+        if !(has_merge_conflict || has_active_changes || introduces_conflict == Some(true)) {
+            continue;
+        }
+        let conflict = FileConflict {
+            worktree: other_wt.id.clone(),
+            branch: other_wt.branch.clone(),
+            has_merge_conflict,
+            has_active_changes,
+            introduces_conflict,
+        };
+        let edit_lines = proposed.and_then(|p| p.lines.as_deref());
+        match edit_lines {
+            Some(lines)
+                if introduces_conflict != Some(true)
+                    && !changes_near(current_wt, other_wt, &repo_relative, lines) =>
+            {
+                distant_conflicts.push(conflict)
+            }
+            _ => conflicts.push(conflict),
         }
     }
 
-    let has_conflicts = !conflicts.is_empty();
-
-    let output = CheckOutput {
+    let claimed_by = foreign_claims(worktrees, current_wt, session, &repo_relative);
+    let mut planned_by: Vec<String> =
+        intent::planned_by_others(worktrees, current_wt, &repo_relative)
+            .into_iter()
+            .map(|p| p.worktree)
+            .collect();
+    planned_by.sort();
+    planned_by.dedup();
+    let recent_edits = foreign_recent_edits(worktrees, current_wt, session, &repo_relative);
+    let messages = Registry::open(worktrees)
+        .messages()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.concerns(&current_wt.id, &repo_relative) && !m.is_acked_by(&current_wt.id))
+        .collect();
+
+    Ok(CheckOutput {
+        schema_version: SCHEMA_VERSION,
         file: repo_relative,
         current_worktree: current_wt.id.clone(),
         current_branch: current_wt.branch.clone(),
         conflicts,
+        distant_conflicts,
+        claimed_by,
+        planned_by,
+        recent_edits,
+        messages,
+    })
+}
+
+/// Lines between an edit and another worktree's change that still count as
+/// the same region of the file
+const EDIT_PROXIMITY: u32 = 3;
+
+/// Whether `other` changed `path` (committed since the merge base or not)
+/// on or near any of `lines` of the current worktree's version.
+///
+/// Errs on the side of "near" when the file can't be diffed.
+fn changes_near(current: &Worktree, other: &Worktree, path: &str, lines: &[LineRange]) -> bool {
+    let Ok(diff) = current.diff_file_against(other, path, Side::Workdir) else {
+        return true;
     };
+    diff.theirs_hunks.iter().any(|theirs| {
+        // Their change in our line numbers
+        let start = diff.map_base_line(theirs.old_start);
+        let end = diff.map_base_line(theirs.old_start + theirs.old_lines);
+        let changed = LineRange {
+            start,
+            lines: end.saturating_sub(start).max(1),
+        };
+        lines.iter().any(|l| l.near(&changed, EDIT_PROXIMITY))
+    })
+}
 
-    // Serialization of simple String/bool fields cannot fail in practice
-    let json = serde_json::to_string_pretty(&output).expect("CheckOutput is always serializable");
-
-    if hook_mode {
-        // Hook mode: output hook decision JSON to stdout so Claude Code prompts the user
-        if has_conflicts {
-            let reason = format_conflict_reason(&output);
-            let hook_output = HookOutput {
-                hook_specific_output: HookDecision {
-                    hook_event_name: "PreToolUse",
-                    permission_decision: "ask",
-                    permission_decision_reason: reason.clone(),
-                    additional_context: Some(reason),
-                },
-            };
-            let hook_json =
-                serde_json::to_string(&hook_output).expect("HookOutput is always serializable");
-            println!("{}", hook_json);
-        }
-    } else {
-        // Manual mode: always output to stdout
-        println!("{}", json);
+/// Look up the conflicting files of a pair in precomputed results
+fn cached_conflicts(
+    pairs: &[WorktreePairConflict],
+    a: &Worktree,
+    b: &Worktree,
+) -> Result<Vec<String>, String> {
+    let pair = pairs
+        .iter()
+        .find(|p| (p.wt1.id == a.id && p.wt2.id == b.id) || (p.wt1.id == b.id && p.wt2.id == a.id))
+        .ok_or_else(|| "no cached result for this pair".to_string())?;
+    match &pair.error {
+        Some(e) => Err(e.clone()),
+        None => Ok(pair.conflicting_files.clone()),
     }
-
-    Ok(has_conflicts)
 }
 
 // ============================================================================
 // Hook stdin reading
 // ============================================================================
 
-/// Read a file path from Claude Code's PreToolUse hook JSON on stdin.
+/// Read an agent's hook payload JSON from stdin.
 ///
-/// Expected format: `{"tool_input": {"file_path": "src/main.rs"}, ...}`
-/// Returns the extracted file_path, or an error if stdin is a TTY,
-/// unreadable, or doesn't contain the expected structure.
-fn read_hook_input() -> Result<String, CheckError> {
+/// Returns an error if stdin is a TTY, unreadable, or not valid JSON.
+/// Extracting the file path is left to the [`Agent`] adapter.
+fn read_hook_payload() -> Result<serde_json::Value, CheckError> {
     let stdin = std::io::stdin();
     if stdin.is_terminal() {
         return Err(CheckError::HookInput(
@@ -215,13 +556,103 @@ fn read_hook_input() -> Result<String, CheckError> {
         .read_to_string(&mut buf)
         .map_err(|e| CheckError::HookInput(format!("failed to read stdin: {}", e)))?;
 
-    let json: serde_json::Value = serde_json::from_str(&buf)
-        .map_err(|e| CheckError::HookInput(format!("invalid JSON on stdin: {}", e)))?;
+    serde_json::from_str(&buf)
+        .map_err(|e| CheckError::HookInput(format!("invalid JSON on stdin: {}", e)))
+}
+
+// ============================================================================
+// Claims
+// ============================================================================
 
-    json["tool_input"]["file_path"]
-        .as_str()
-        .map(|s| s.to_string())
-        .ok_or_else(|| CheckError::HookInput("stdin JSON missing tool_input.file_path".to_string()))
+/// Find claims covering `path` that are held by someone other than the caller.
+///
+/// Claims from worktrees that no longer exist are ignored, and an unreadable
+/// registry is treated as having no claims.
+fn foreign_claims(
+    worktrees: &WorktreeManager,
+    current_wt: &Worktree,
+    session: Option<&str>,
+    path: &str,
+) -> Vec<ClaimInfo> {
+    let claims = Registry::open(worktrees).claims().unwrap_or_default();
+    claims
+        .into_iter()
+        .filter(|c| !c.is_held_by(&current_wt.id, session))
+        .filter(|c| worktrees.iter().any(|wt| wt.id == c.worktree))
+        .filter(|c| c.matches(path))
+        .map(|c| ClaimInfo {
+            worktree: c.worktree,
+            session: c.session,
+            pattern: c.pattern,
+        })
+        .collect()
+}
+
+// ============================================================================
+// Edit ledger
+// ============================================================================
+
+/// How far back the ledger counts as "recently edited"
+const RECENT_EDIT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Latest recent ledger entry for `path` from every session other than the
+/// caller's.
+///
+/// Entries from the caller's worktree count as its own when either side has
+/// no session, like claims. An unreadable ledger is treated as empty.
+fn foreign_recent_edits(
+    worktrees: &WorktreeManager,
+    current_wt: &Worktree,
+    session: Option<&str>,
+    path: &str,
+) -> Vec<LedgerEntry> {
+    let entries = Registry::open(worktrees)
+        .recent_edits(Some(path), RECENT_EDIT_WINDOW)
+        .unwrap_or_default();
+    let mut latest: Vec<LedgerEntry> = Vec::new();
+    // Entries are oldest first, so later ones replace earlier ones
+    for entry in entries {
+        let own = entry.worktree == current_wt.id
+            && match (entry.session.as_deref(), session) {
+                (Some(theirs), Some(ours)) => theirs == ours,
+                _ => true,
+            };
+        if own {
+            continue;
+        }
+        latest.retain(|e| !(e.worktree == entry.worktree && e.session == entry.session));
+        latest.push(entry);
+    }
+    latest
+}
+
+/// Record the files a hook reported in the edit ledger.
+///
+/// The ledger is best-effort: failing to write it never affects the hook.
+fn record_edits(worktrees: &WorktreeManager, entries: Vec<LedgerEntry>) {
+    let _ = Registry::open(worktrees).record_edits(&entries);
+}
+
+/// Record an edit reported by a post-edit hook as completed
+fn record_completed(
+    agent: Agent,
+    payload: &serde_json::Value,
+    worktrees: &WorktreeManager,
+) -> Result<(), CheckError> {
+    let input = agent.parse(payload)?;
+    let tool = agent.tool_name(payload);
+    let entries = input
+        .file_paths
+        .iter()
+        .filter_map(|path| resolve_file_path(path, worktrees).ok())
+        .map(|(wt, relative)| LedgerEntry {
+            session: input.session_id.clone(),
+            tool: tool.clone(),
+            ..LedgerEntry::new(&wt.id, &relative, EditPhase::Completed)
+        })
+        .collect();
+    record_edits(worktrees, entries);
+    Ok(())
 }
 
 // ============================================================================
@@ -232,13 +663,49 @@ fn read_hook_input() -> Result<String, CheckError> {
 fn format_conflict_reason(output: &CheckOutput) -> String {
     let mut parts: Vec<String> = Vec::new();
     for c in &output.conflicts {
-        let kind = match (c.has_merge_conflict, c.has_active_changes) {
-            (true, true) => "merge conflict + active changes",
-            (true, false) => "merge conflict",
-            (false, true) => "active changes",
-            (false, false) => continue,
+        let mut kinds = Vec::new();
+        if c.has_merge_conflict {
+            kinds.push("merge conflict");
+        }
+        if c.introduces_conflict == Some(true) {
+            kinds.push("this edit would cause a merge conflict");
+        }
+        if c.has_active_changes {
+            kinds.push("active changes");
+        }
+        if kinds.is_empty() {
+            continue;
+        }
+        parts.push(format!(
+            "{} [{}]: {}",
+            c.worktree,
+            c.branch,
+            kinds.join(" + ")
+        ));
+    }
+    for c in &output.distant_conflicts {
+        parts.push(format!(
+            "{} [{}]: changes elsewhere in the file",
+            c.worktree, c.branch
+        ));
+    }
+    for c in &output.claimed_by {
+        parts.push(format!("{}: claimed ({})", c.worktree, c.pattern));
+    }
+    for wt in &output.planned_by {
+        parts.push(format!("{}: planned edit", wt));
+    }
+    for e in &output.recent_edits {
+        let when = match e.age().map_or(0, |age| age.as_secs() / 60) {
+            0 => "just now".to_string(),
+            minutes => format!("{} min ago", minutes),
         };
-        parts.push(format!("{} [{}]: {}", c.worktree, c.branch, kind));
+        let session = e
+            .session
+            .as_deref()
+            .map(|s| format!(" (session {})", s))
+            .unwrap_or_default();
+        parts.push(format!("{}{}: edited {}", e.worktree, session, when));
     }
     format!(
         "Conflicts on {} with {} worktree(s):\n{}",
@@ -248,6 +715,32 @@ fn format_conflict_reason(output: &CheckOutput) -> String {
     )
 }
 
+/// List the unread messages for the checked files, once each
+fn format_messages(outputs: &[CheckOutput]) -> String {
+    let mut seen = Vec::new();
+    let mut lines = Vec::new();
+    for m in outputs.iter().flat_map(|o| &o.messages) {
+        if seen.contains(&m.id) {
+            continue;
+        }
+        seen.push(m.id);
+        lines.push(format!(
+            "#{} from {} ({}): {}",
+            m.id,
+            m.from,
+            crate::msg::describe_recipient(&m.to),
+            m.body
+        ));
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!(
+        "Messages from other worktrees (mark read with `clash msg ack <id>`):\n{}",
+        lines.join("\n")
+    )
+}
+
 // ============================================================================
 // Path resolution
 // ============================================================================
@@ -262,7 +755,7 @@ fn format_conflict_reason(output: &CheckOutput) -> String {
 /// 2. Canonicalize if possible (resolves symlinks and `..` components)
 /// 3. Walk up the path to find the containing worktree
 /// 4. Strip the worktree prefix to get the repo-relative path
-fn resolve_file_path<'a>(
+pub fn resolve_file_path<'a>(
     path: &str,
     worktrees: &'a WorktreeManager,
 ) -> Result<(&'a Worktree, String), CheckError> {
@@ -302,20 +795,10 @@ fn resolve_file_path<'a>(
 ///
 /// Compares the file on disk against HEAD. Returns true if the file
 /// differs from HEAD (modified, new, or deleted).
-fn file_has_active_changes(worktree_path: &Path, file_path: &str) -> bool {
-    let repo = match gix::open(worktree_path) {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
-
-    let workdir = match repo.workdir() {
-        Some(p) => p.to_path_buf(),
-        None => return false,
-    };
-
-    let disk_path = workdir.join(file_path);
+fn file_has_active_changes(worktree: &Worktree, file_path: &str) -> bool {
+    let disk_path = worktree.path.join(file_path);
     let exists_on_disk = disk_path.exists();
-    let head_blob = head_file_contents(&repo, file_path);
+    let head_blob = worktree.head_file_contents(file_path);
 
     match (head_blob, exists_on_disk) {
         (None, false) => false,   // Not tracked, not on disk
@@ -331,13 +814,80 @@ fn file_has_active_changes(worktree_path: &Path, file_path: &str) -> bool {
     }
 }
 
-/// Read a file's contents from HEAD in the given repository.
-fn head_file_contents(repo: &gix::Repository, file_path: &str) -> Option<Vec<u8>> {
-    let mut head = repo.head().ok()?;
-    let head_id = head.try_peel_to_id().ok()??;
-    let commit = repo.find_object(head_id).ok()?.try_into_commit().ok()?;
-    let mut tree = commit.tree().ok()?;
-    let entry = tree.peel_to_entry_by_path(file_path).ok()??;
-    let blob = repo.find_object(entry.id()).ok()?;
-    Some(blob.data.to_vec())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    #[test]
+    fn edit_introduces_conflict_only_when_the_workdir_had_none() {
+        let repo = TestRepo::new(&[("a.txt", "1\n2\n3\n")]);
+        repo.add_worktree("feature");
+        repo.write("feature", "a.txt", "1\nX\n3\n");
+        repo.commit("feature", "change line 2");
+        let worktrees = repo.manager();
+        let path = repo.path("main").join("a.txt");
+        let introduces = |content: &str| {
+            let proposal = Proposal {
+                content: content.to_string(),
+                lines: None,
+            };
+            let path = path.to_str().unwrap();
+            let output = check_file(
+                &worktrees,
+                path,
+                None,
+                &Suppressions::none(),
+                None,
+                Some(&proposal),
+            )
+            .unwrap();
+            output
+                .conflicts
+                .iter()
+                .any(|c| c.introduces_conflict == Some(true))
+        };
+
+        assert!(introduces("1\nY\n3\n"));
+
+        // Uncommitted work already conflicts, so the next edit doesn't introduce it
+        repo.write("main", "a.txt", "1\nY\n3\n");
+        assert!(!introduces("1\nY\n3 and more\n"));
+    }
+
+    fn write_payload(repo: &TestRepo, file: &Path) -> serde_json::Value {
+        serde_json::json!({
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Write",
+            "cwd": repo.path("main"),
+            "tool_input": { "file_path": file, "content": "x\n" },
+        })
+    }
+
+    #[test]
+    fn edits_outside_the_repository_pass_even_when_failing_closed() {
+        let repo = TestRepo::new(&[(".clash.toml", "[policy]\non_error = \"deny\"\n")]);
+        let outside = tempfile::tempdir().unwrap();
+        for file in [outside.path().join("x"), outside.path().join("new/dir/x")] {
+            let response = hook_response(Agent::Claude, &write_payload(&repo, &file), false);
+            assert_eq!(response.unwrap(), HookResponse::default());
+        }
+    }
+
+    #[test]
+    fn unreadable_config_asks_about_every_edit() {
+        let repo = TestRepo::new(&[("a.txt", "a\n")]);
+        repo.write("main", ".clash.toml", "[policy\n");
+        let file = repo.path("main").join("a.txt");
+        let response = hook_response(Agent::Claude, &write_payload(&repo, &file), false).unwrap();
+        let stdout: serde_json::Value = serde_json::from_str(&response.stdout.unwrap()).unwrap();
+        let output = &stdout["hookSpecificOutput"];
+        assert_eq!(output["permissionDecision"], "ask");
+        assert!(
+            output["permissionDecisionReason"]
+                .as_str()
+                .unwrap()
+                .contains(".clash.toml")
+        );
+    }
 }
@@ -0,0 +1,464 @@
+//! Hook adapters for different coding agents
+//!
+//! Every agent sends its own pre-edit hook payload on stdin and expects its
+//! own response. An [`Agent`] extracts the file being edited from the
+//! payload and renders clash's [`Decision`] in the agent's native format, so
+//! one `clash check` binary can guard every agent.
+//!
+//! Shell tool payloads are accepted too: the files the command would modify
+//! are extracted by [`shell::write_targets`](super::shell::write_targets).
+//! So are patches (`apply_patch`, unified diffs), via
+//! [`patch::touched_paths`](super::patch::touched_paths).
+
+use super::CheckError;
+use super::edit::{ProposedEdit, Replacement};
+use super::{patch, shell};
+use clash_sh::output::{CheckOutput, Decision, HookOutput, SCHEMA_VERSION};
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// Coding agents whose hook formats clash understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Agent {
+    /// Claude Code `PreToolUse` hooks
+    Claude,
+    /// OpenAI Codex CLI hooks (Claude-compatible payload and response)
+    Codex,
+    /// Cursor `beforeShellExecution` / `afterFileEdit` hooks
+    Cursor,
+    /// Gemini CLI `BeforeTool` hooks
+    Gemini,
+    /// Windsurf Cascade `pre_write_code` hooks
+    Windsurf,
+    /// Any other agent: `{"file_path": ...}` in, clash's own JSON decision out
+    Generic,
+}
+
+/// Fields extracted from a pre-edit hook payload
+#[derive(Debug, PartialEq)]
+pub struct HookInput {
+    /// Files the tool will write, move or delete
+    pub file_paths: Vec<String>,
+    pub session_id: Option<String>,
+    /// The shell command the paths were extracted from, for shell tools
+    pub command: Option<String>,
+    /// Paths were extracted from a command or patch rather than named by the
+    /// agent, so some may lie outside the repository
+    pub extracted: bool,
+    /// The change about to be made, when the payload carries it
+    pub edit: Option<ProposedEdit>,
+}
+
+/// What to print and how to exit so the agent understands the decision
+#[derive(Debug, Default, PartialEq)]
+pub struct HookResponse {
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub exit_code: i32,
+}
+
+/// Tool names Codex uses for edits and shell commands
+const CODEX_TOOLS: &[&str] = &["apply_patch", "shell", "local_shell", "exec_command"];
+
+/// Tool names that run a shell command rather than edit a file
+const SHELL_TOOLS: &[&str] = &[
+    "Bash",
+    "shell",
+    "local_shell",
+    "exec_command",
+    "run_shell_command",
+];
+
+impl Agent {
+    /// Guess the agent from the shape of its hook payload
+    pub fn detect(payload: &Value) -> Self {
+        if payload.get("agent_action_name").is_some() || payload.get("tool_info").is_some() {
+            return Agent::Windsurf;
+        }
+        if payload.get("conversation_id").is_some() && payload.get("workspace_roots").is_some() {
+            return Agent::Cursor;
+        }
+        match payload["hook_event_name"].as_str() {
+            Some("BeforeTool" | "AfterTool") => Agent::Gemini,
+            Some("PreToolUse" | "PostToolUse")
+                if payload["tool_name"]
+                    .as_str()
+                    .is_some_and(|t| CODEX_TOOLS.contains(&t)) =>
+            {
+                Agent::Codex
+            }
+            Some("PreToolUse" | "PostToolUse") => Agent::Claude,
+            _ if payload.get("tool_input").is_some() => Agent::Claude,
+            _ => Agent::Generic,
+        }
+    }
+
+    /// Extract the file paths and session from the agent's payload.
+    ///
+    /// For shell tools the paths are the command's write targets, which may
+    /// be none at all. Relative paths are resolved against the payload's
+    /// `cwd` (or Cursor's first workspace root) when the agent provides one.
+    pub fn parse(self, payload: &Value) -> Result<HookInput, CheckError> {
+        let tool_input = &payload["tool_input"];
+        let base = first_str(&[
+            &payload["cwd"],
+            &payload["tool_info"]["cwd"],
+            &payload["workspace_roots"][0],
+        ]);
+        let resolve = |path: String| match &base {
+            Some(base) if Path::new(&path).is_relative() => {
+                Path::new(base).join(&path).to_string_lossy().into_owned()
+            }
+            _ => path,
+        };
+
+        if let Some(patch) = self.patch_text(payload) {
+            return Ok(HookInput {
+                file_paths: patch::touched_paths(&patch)
+                    .into_iter()
+                    .map(resolve)
+                    .collect(),
+                session_id: self.session_id(payload),
+                command: None,
+                extracted: true,
+                edit: None,
+            });
+        }
+
+        if let Some(command) = self.shell_command(payload) {
+            // Patches are often applied through the shell (`apply_patch <<EOF`,
+            // `git apply`), with the patch inline in the command
+            let mut targets =
+                shell::write_targets(&command, Path::new(base.as_deref().unwrap_or(".")));
+            for path in patch::touched_paths(&command) {
+                if !targets.contains(&path) {
+                    targets.push(path);
+                }
+            }
+            return Ok(HookInput {
+                file_paths: targets.into_iter().map(resolve).collect(),
+                session_id: self.session_id(payload),
+                command: Some(command),
+                extracted: true,
+                edit: None,
+            });
+        }
+
+        // Cursor names a file only after editing it; other events that carry
+        // one (`beforeReadFile`) are reads, which change nothing
+        if self == Agent::Cursor && !self.is_post_edit(payload) {
+            return Ok(HookInput {
+                file_paths: Vec::new(),
+                session_id: self.session_id(payload),
+                command: None,
+                extracted: false,
+                edit: None,
+            });
+        }
+
+        let (file_path, field) = match self {
+            Agent::Claude | Agent::Codex | Agent::Gemini => (
+                first_str(&[
+                    &tool_input["file_path"],
+                    &tool_input["path"],
+                    &tool_input["absolute_path"],
+                ]),
+                "tool_input.file_path",
+            ),
+            Agent::Cursor => (first_str(&[&payload["file_path"]]), "file_path"),
+            Agent::Windsurf => (
+                first_str(&[&payload["tool_info"]["file_path"]]),
+                "tool_info.file_path",
+            ),
+            Agent::Generic => (
+                first_str(&[
+                    &payload["file_path"],
+                    &payload["path"],
+                    &tool_input["file_path"],
+                ]),
+                "file_path",
+            ),
+        };
+        let file_path = file_path.ok_or_else(|| {
+            CheckError::HookInput(format!("stdin JSON missing {} ({:?} hook)", field, self))
+        })?;
+
+        Ok(HookInput {
+            file_paths: vec![resolve(file_path)],
+            session_id: self.session_id(payload),
+            command: None,
+            extracted: false,
+            edit: self.proposed_edit(payload),
+        })
+    }
+
+    /// The new content or replacements from a Write/Edit style payload
+    fn proposed_edit(self, payload: &Value) -> Option<ProposedEdit> {
+        match self {
+            Agent::Claude | Agent::Codex | Agent::Gemini => proposed_edit(&payload["tool_input"]),
+            Agent::Windsurf => proposed_edit(&payload["tool_info"]),
+            Agent::Generic => {
+                proposed_edit(payload).or_else(|| proposed_edit(&payload["tool_input"]))
+            }
+            // Cursor's hooks don't include the edit
+            Agent::Cursor => None,
+        }
+    }
+
+    /// Whether the payload reports an edit that already happened (a
+    /// post-edit hook) rather than one about to happen
+    pub fn is_post_edit(self, payload: &Value) -> bool {
+        match self {
+            Agent::Claude | Agent::Codex | Agent::Generic => {
+                payload["hook_event_name"] == "PostToolUse"
+            }
+            Agent::Gemini => payload["hook_event_name"] == "AfterTool",
+            Agent::Cursor => payload["hook_event_name"] == "afterFileEdit",
+            Agent::Windsurf => payload["agent_action_name"] == "post_write_code",
+        }
+    }
+
+    /// The tool making the edit, as the agent names it
+    pub fn tool_name(self, payload: &Value) -> Option<String> {
+        let field = match self {
+            Agent::Claude | Agent::Codex | Agent::Gemini | Agent::Generic => &payload["tool_name"],
+            Agent::Cursor => &payload["hook_event_name"],
+            Agent::Windsurf => &payload["agent_action_name"],
+        };
+        first_str(&[field])
+    }
+
+    fn session_id(self, payload: &Value) -> Option<String> {
+        let field = match self {
+            Agent::Claude | Agent::Codex | Agent::Gemini | Agent::Generic => "session_id",
+            Agent::Cursor => "conversation_id",
+            Agent::Windsurf => "trajectory_id",
+        };
+        first_str(&[&payload[field]])
+    }
+
+    /// The patch text if the payload is for a patch tool (Codex `apply_patch`)
+    fn patch_text(self, payload: &Value) -> Option<String> {
+        let tool_input = &payload["tool_input"];
+        match self {
+            Agent::Claude | Agent::Codex | Agent::Gemini
+                if payload["tool_name"].as_str() == Some("apply_patch") =>
+            {
+                first_str(&[&tool_input["input"], &tool_input["patch"]])
+            }
+            Agent::Generic => first_str(&[&payload["patch"]]),
+            _ => None,
+        }
+    }
+
+    /// The command line if the payload is for a shell tool rather than an edit
+    fn shell_command(self, payload: &Value) -> Option<String> {
+        let tool_input = &payload["tool_input"];
+        let command = match self {
+            Agent::Claude | Agent::Codex | Agent::Gemini => {
+                let tool = payload["tool_name"].as_str()?;
+                if !SHELL_TOOLS.contains(&tool) {
+                    return None;
+                }
+                if tool_input["command"].is_null() {
+                    &tool_input["cmd"]
+                } else {
+                    &tool_input["command"]
+                }
+            }
+            Agent::Cursor | Agent::Generic => &payload["command"],
+            Agent::Windsurf => &payload["tool_info"]["command_line"],
+        };
+        match command {
+            Value::String(s) => Some(s.clone()),
+            // Codex passes argv, usually `["bash", "-lc", "<script>"]`
+            Value::Array(argv) => {
+                let argv: Vec<&str> = argv.iter().filter_map(Value::as_str).collect();
+                match argv.as_slice() {
+                    [_, flag, script] if flag.ends_with('c') && flag.starts_with('-') => {
+                        Some(script.to_string())
+                    }
+                    _ => Some(argv.join(" ")),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Render a decision in the agent's native response format.
+    ///
+    /// An "allow" with a non-empty `reason` still lets the edit through but
+    /// passes the reason on to the agent where the format allows it.
+    /// `context` is the reason plus details meant for the agent only (the
+    /// other worktrees' changes, messages left for it), sent where the
+    /// format has a separate agent-facing field.
+    pub fn respond(
+        self,
+        decision: Decision,
+        reason: &str,
+        context: &str,
+        outputs: &[CheckOutput],
+    ) -> HookResponse {
+        let verdict = decision.as_str();
+        let silent = decision == Decision::Allow && context.is_empty();
+        match self {
+            // Nothing on stdout lets the edit proceed with the normal permission flow
+            Agent::Claude | Agent::Codex if silent => HookResponse::default(),
+            // Context only, without a permission decision that would skip the prompt
+            Agent::Claude | Agent::Codex if decision == Decision::Allow => HookResponse {
+                stdout: Some(
+                    json!({
+                        "hookSpecificOutput": {
+                            "hookEventName": "PreToolUse",
+                            "additionalContext": context,
+                        }
+                    })
+                    .to_string(),
+                ),
+                ..Default::default()
+            },
+            Agent::Claude | Agent::Codex => HookResponse {
+                stdout: Some(
+                    json!({
+                        "hookSpecificOutput": {
+                            "hookEventName": "PreToolUse",
+                            "permissionDecision": verdict,
+                            "permissionDecisionReason": reason,
+                            "additionalContext": context,
+                        }
+                    })
+                    .to_string(),
+                ),
+                ..Default::default()
+            },
+            Agent::Cursor if silent => HookResponse {
+                stdout: Some(json!({ "permission": "allow" }).to_string()),
+                ..Default::default()
+            },
+            Agent::Cursor if decision == Decision::Allow => HookResponse {
+                stdout: Some(json!({ "permission": "allow", "agentMessage": context }).to_string()),
+                ..Default::default()
+            },
+            Agent::Cursor => HookResponse {
+                stdout: Some(
+                    json!({
+                        "permission": verdict,
+                        "userMessage": reason,
+                        "agentMessage": context,
+                    })
+                    .to_string(),
+                ),
+                ..Default::default()
+            },
+            Agent::Gemini if decision == Decision::Allow && reason.is_empty() => {
+                HookResponse::default()
+            }
+            Agent::Gemini => HookResponse {
+                stdout: Some(json!({ "decision": verdict, "reason": reason }).to_string()),
+                ..Default::default()
+            },
+            // Windsurf has no "ask": exit code 2 blocks the write and shows
+            // stderr, anything else lets it through
+            Agent::Windsurf => match decision {
+                _ if silent => HookResponse::default(),
+                Decision::Allow | Decision::Ask => HookResponse {
+                    stdout: Some(context.to_string()),
+                    ..Default::default()
+                },
+                Decision::Deny => HookResponse {
+                    stderr: Some(reason.to_string()),
+                    exit_code: 2,
+                    ..Default::default()
+                },
+            },
+            Agent::Generic => HookResponse {
+                stdout: serde_json::to_string(&HookOutput {
+                    schema_version: SCHEMA_VERSION,
+                    decision,
+                    reason: (!reason.is_empty()).then(|| reason.to_string()),
+                    context: (context != reason).then(|| context.to_string()),
+                    checks: outputs.to_vec(),
+                })
+                .ok(),
+                exit_code: if decision == Decision::Deny { 2 } else { 0 },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Read `content`, `edits[]` or `old_string`/`new_string` from a tool input
+fn proposed_edit(input: &Value) -> Option<ProposedEdit> {
+    if let Some(content) = input["content"].as_str() {
+        return Some(ProposedEdit::Write(content.to_string()));
+    }
+    let replacement = |edit: &Value| {
+        Some(Replacement {
+            old: edit["old_string"].as_str()?.to_string(),
+            new: edit["new_string"].as_str()?.to_string(),
+            // Gemini CLI's replace tool counts occurrences instead
+            all: edit["replace_all"].as_bool().unwrap_or(false)
+                || edit["expected_replacements"]
+                    .as_u64()
+                    .is_some_and(|n| n > 1),
+        })
+    };
+    let replacements = match input["edits"].as_array() {
+        Some(edits) => edits.iter().map(replacement).collect::<Option<Vec<_>>>()?,
+        None => vec![replacement(input)?],
+    };
+    Some(ProposedEdit::Replace(replacements))
+}
+
+/// First value that is a non-empty string
+fn first_str(values: &[&Value]) -> Option<String> {
+    values
+        .iter()
+        .find_map(|v| v.as_str().filter(|s| !s.is_empty()))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_agents_from_payload_shape() {
+        let claude = json!({"hook_event_name": "PreToolUse", "tool_name": "Edit", "tool_input": {"file_path": "/r/a.rs"}});
+        let codex =
+            json!({"hook_event_name": "PreToolUse", "tool_name": "apply_patch", "tool_input": {}});
+        let cursor = json!({"hook_event_name": "afterFileEdit", "conversation_id": "c", "workspace_roots": ["/r"], "file_path": "a.rs"});
+        let gemini = json!({"hook_event_name": "BeforeTool", "tool_input": {"file_path": "a.rs"}});
+        let windsurf =
+            json!({"agent_action_name": "pre_write_code", "tool_info": {"file_path": "/r/a.rs"}});
+
+        assert_eq!(Agent::detect(&claude), Agent::Claude);
+        assert_eq!(Agent::detect(&codex), Agent::Codex);
+        assert_eq!(Agent::detect(&cursor), Agent::Cursor);
+        assert_eq!(Agent::detect(&gemini), Agent::Gemini);
+        assert_eq!(Agent::detect(&windsurf), Agent::Windsurf);
+        assert_eq!(Agent::detect(&json!({"file_path": "a.rs"})), Agent::Generic);
+
+        let input = Agent::Cursor.parse(&cursor).unwrap();
+        assert_eq!(
+            input.file_paths,
+            vec![Path::new("/r").join("a.rs").to_string_lossy()]
+        );
+        assert_eq!(input.session_id.as_deref(), Some("c"));
+        let read = json!({"hook_event_name": "beforeReadFile", "conversation_id": "c", "workspace_roots": ["/r"], "file_path": "a.rs"});
+        assert!(Agent::Cursor.parse(&read).unwrap().file_paths.is_empty());
+
+        let codex_shell = json!({
+            "hook_event_name": "PreToolUse",
+            "tool_name": "shell",
+            "cwd": "/r",
+            "tool_input": {"command": ["bash", "-lc", "sed -i 's/a/b/' a.rs"]},
+        });
+        let input = Agent::detect(&codex_shell).parse(&codex_shell).unwrap();
+        assert_eq!(input.command.as_deref(), Some("sed -i 's/a/b/' a.rs"));
+        assert_eq!(
+            input.file_paths,
+            vec![Path::new("/r").join("a.rs").to_string_lossy()]
+        );
+    }
+}
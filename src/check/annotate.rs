@@ -0,0 +1,97 @@
+//! Check results as CI annotations (see [`crate::format`])
+//!
+//! Each conflicting worktree becomes one annotation per region of the
+//! checked file it collides with, in the current worktree's on-disk version.
+
+use crate::format::{self, Annotation, Level, Rule};
+use clash_sh::output::CheckOutput;
+use clash_sh::{Side, WorktreeManager};
+
+/// Annotations for every finding in the check results.
+///
+/// Without `worktrees` (or when a file can't be diffed) the findings are
+/// reported on the whole file.
+pub fn annotations(
+    worktrees: Option<&WorktreeManager>,
+    outputs: &[CheckOutput],
+) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    for output in outputs {
+        let current = worktrees.and_then(|w| w.find(&output.current_worktree).ok());
+        let lines_for = |other: &str| -> Vec<Option<(u32, u32)>> {
+            let ranges = current
+                .zip(worktrees.and_then(|w| w.find(other).ok()))
+                .and_then(|(current, other)| {
+                    current
+                        .diff_file_against(other, &output.file, Side::Workdir)
+                        .ok()
+                })
+                .map(|diff| format::conflict_lines(&diff))
+                .unwrap_or_default();
+            if ranges.is_empty() {
+                vec![None]
+            } else {
+                ranges.into_iter().map(Some).collect()
+            }
+        };
+        let mut push = |rule, level, lines, title: String, message: String| {
+            annotations.push(Annotation {
+                suite: output.file.clone(),
+                rule,
+                level,
+                file: Some(output.file.clone()),
+                lines,
+                title,
+                message,
+            })
+        };
+
+        for c in &output.conflicts {
+            let merge = c.has_merge_conflict || c.introduces_conflict == Some(true);
+            let (rule, level, what) = if merge {
+                (Rule::MergeConflict, Level::Error, "Merge conflict")
+            } else {
+                (Rule::ActiveChanges, Level::Warning, "Active changes")
+            };
+            for lines in lines_for(&c.worktree) {
+                push(
+                    rule,
+                    level,
+                    lines,
+                    format!("{} with {} [{}]", what, c.worktree, c.branch),
+                    format!("{} [{}] also changes these lines", c.worktree, c.branch),
+                );
+            }
+        }
+        for c in &output.distant_conflicts {
+            for lines in lines_for(&c.worktree) {
+                push(
+                    Rule::DistantConflict,
+                    Level::Notice,
+                    lines,
+                    format!("Changed in {} [{}]", c.worktree, c.branch),
+                    format!("{} [{}] changes these lines", c.worktree, c.branch),
+                );
+            }
+        }
+        for c in &output.claimed_by {
+            push(
+                Rule::Claimed,
+                Level::Error,
+                None,
+                format!("Claimed by {}", c.worktree),
+                format!("{} claimed {}", c.worktree, c.pattern),
+            );
+        }
+        for wt in &output.planned_by {
+            push(
+                Rule::Planned,
+                Level::Warning,
+                None,
+                format!("Planned by {}", wt),
+                format!("{} plans to edit this file", wt),
+            );
+        }
+    }
+    annotations
+}
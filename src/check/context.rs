@@ -0,0 +1,119 @@
+//! Detail for the agent about what its edit collides with
+//!
+//! A one-line "merge conflict" reason tells the agent to stop, not how to
+//! adapt. For each conflicting worktree this adds the other branch's latest
+//! commits, what it changed in the file since the merge base (committed or
+//! not) and where that collides with our own changes, within a size budget
+//! so the hook response stays small.
+
+use clash_sh::output::CheckOutput;
+use clash_sh::{Hunk, Side, WorktreeManager};
+use std::fmt::Write;
+
+/// Upper bound on the detail added to a hook response, in bytes
+const CONTEXT_BUDGET: usize = 4000;
+
+/// Latest commits listed per other branch
+const COMMIT_LIMIT: usize = 3;
+
+/// Describe every conflicting worktree's changes to the checked files.
+///
+/// Worktrees or files that can't be read are skipped, and the result is
+/// cut off at [`CONTEXT_BUDGET`].
+pub fn describe(worktrees: &WorktreeManager, outputs: &[CheckOutput]) -> String {
+    let mut text = String::new();
+    for output in outputs {
+        let Ok(current) = worktrees.find(&output.current_worktree) else {
+            continue;
+        };
+        let others = output.conflicts.iter().chain(&output.distant_conflicts);
+        for other in others.filter_map(|c| worktrees.find(&c.worktree).ok()) {
+            let Ok(diff) = current.diff_file_against(other, &output.file, Side::Workdir) else {
+                continue;
+            };
+
+            let mut section = format!("{} in {} [{}]:\n", output.file, other.id, other.branch);
+            let commits = other.recent_commits(COMMIT_LIMIT);
+            if !commits.is_empty() {
+                section.push_str("Latest commits:\n");
+                for commit in commits {
+                    let short = commit.id.get(..7).unwrap_or(&commit.id);
+                    let _ = writeln!(section, "  {} {}", short, commit.summary);
+                }
+            }
+            if !diff.theirs_hunks.is_empty() {
+                section.push_str("Their changes since the merge base:\n");
+                for hunk in &diff.theirs_hunks {
+                    write_hunk(&mut section, hunk);
+                }
+            }
+            for region in diff.regions(0) {
+                let _ = writeln!(
+                    section,
+                    "Collides with your change at base lines {}-{}:",
+                    region.base_start,
+                    region.base_start + region.base_lines.max(1) - 1
+                );
+                write_hunk(&mut section, &region.ours);
+            }
+
+            if !push_within_budget(&mut text, &section) {
+                return text;
+            }
+        }
+    }
+    text
+}
+
+/// Append a hunk in unified diff style
+fn write_hunk(out: &mut String, hunk: &Hunk) {
+    let _ = writeln!(
+        out,
+        "@@ -{},{} +{},{} @@",
+        hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+    );
+    for line in &hunk.removed {
+        let _ = writeln!(out, "-{}", line);
+    }
+    for line in &hunk.added {
+        let _ = writeln!(out, "+{}", line);
+    }
+}
+
+/// Append `section` (separated by a blank line) if it fits in the budget,
+/// otherwise as much of it as fits. Returns false once the budget is spent.
+fn push_within_budget(text: &mut String, section: &str) -> bool {
+    const TRUNCATED: &str = "\n... (truncated)";
+    let separator = if text.is_empty() { "" } else { "\n" };
+    let remaining = CONTEXT_BUDGET.saturating_sub(text.len() + separator.len());
+    if section.len() <= remaining {
+        text.push_str(separator);
+        text.push_str(section);
+        return true;
+    }
+
+    let mut end = remaining.saturating_sub(TRUNCATED.len());
+    while !section.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.push_str(separator);
+    text.push_str(&section[..end]);
+    text.push_str(TRUNCATED);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_truncates_on_char_boundary() {
+        let mut text = String::new();
+        assert!(push_within_budget(&mut text, "short\n"));
+
+        let long = "é".repeat(CONTEXT_BUDGET);
+        assert!(!push_within_budget(&mut text, &long));
+        assert!(text.len() <= CONTEXT_BUDGET);
+        assert!(text.ends_with("(truncated)"));
+    }
+}
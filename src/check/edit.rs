@@ -0,0 +1,158 @@
+//! Proposed edits carried by pre-edit hook payloads
+//!
+//! Write tools send the whole new file and Edit tools send string
+//! replacements. Applying them to the file on disk gives the content the
+//! file would have after the edit, which [`check_file`](super::check_file)
+//! merges against every other worktree to see whether this particular edit
+//! introduces a conflict. Replacements also pinpoint the lines they touch,
+//! so conflicts elsewhere in the file don't have to interrupt the agent.
+
+use serde::{Deserialize, Serialize};
+
+/// The change an agent is about to make to one file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProposedEdit {
+    /// Replace the whole file
+    Write(String),
+    /// Apply string replacements in order
+    Replace(Vec<Replacement>),
+}
+
+/// One `old_string` → `new_string` replacement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replacement {
+    pub old: String,
+    pub new: String,
+    /// Replace every occurrence instead of only the first
+    pub all: bool,
+}
+
+/// A run of lines in the current version of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    /// First line (1-based)
+    pub start: u32,
+    /// Number of lines, at least 1
+    pub lines: u32,
+}
+
+impl LineRange {
+    /// Whether `other` overlaps this range or lies within `slack` lines of it
+    pub fn near(&self, other: &LineRange, slack: u32) -> bool {
+        let (a0, a1) = (self.start, self.start + self.lines);
+        let (b0, b1) = (other.start, other.start + other.lines);
+        a0 <= b1 + slack && b0 <= a1 + slack
+    }
+}
+
+impl ProposedEdit {
+    /// The file content after the edit, given the current content.
+    ///
+    /// Returns `None` if a replacement's `old_string` isn't in the file, in
+    /// which case the agent's edit will fail anyway.
+    pub fn apply(&self, current: Option<&str>) -> Option<String> {
+        match self {
+            ProposedEdit::Write(content) => Some(content.clone()),
+            ProposedEdit::Replace(replacements) => {
+                let mut text = current.unwrap_or_default().to_string();
+                for r in replacements {
+                    if r.old.is_empty() {
+                        // Creating a file with an empty old_string
+                        if !text.is_empty() {
+                            return None;
+                        }
+                        text = r.new.clone();
+                    } else if !text.contains(&r.old) {
+                        return None;
+                    } else if r.all {
+                        text = text.replace(&r.old, &r.new);
+                    } else {
+                        text = text.replacen(&r.old, &r.new, 1);
+                    }
+                }
+                Some(text)
+            }
+        }
+    }
+
+    /// Lines of `current` that the edit replaces.
+    ///
+    /// Returns `None` for whole-file writes, and when a replacement's
+    /// `old_string` can't be found in `current` (e.g. it only exists after
+    /// an earlier replacement in the same edit).
+    pub fn line_ranges(&self, current: &str) -> Option<Vec<LineRange>> {
+        let ProposedEdit::Replace(replacements) = self else {
+            return None;
+        };
+        let mut ranges = Vec::new();
+        for r in replacements {
+            if r.old.is_empty() {
+                return None;
+            }
+            let mut found = current.match_indices(&r.old).map(|(pos, _)| pos).peekable();
+            found.peek()?;
+            for pos in found.take(if r.all { usize::MAX } else { 1 }) {
+                let start = current[..pos].matches('\n').count() as u32 + 1;
+                let lines = r.old.trim_end_matches('\n').matches('\n').count() as u32 + 1;
+                ranges.push(LineRange { start, lines });
+            }
+        }
+        Some(ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_replacements_in_order() {
+        let edit = ProposedEdit::Replace(vec![
+            Replacement {
+                old: "a".into(),
+                new: "b".into(),
+                all: false,
+            },
+            Replacement {
+                old: "b".into(),
+                new: "c".into(),
+                all: true,
+            },
+        ]);
+        assert_eq!(edit.apply(Some("a a b")).as_deref(), Some("c a c"));
+        assert_eq!(edit.apply(Some("x")), None);
+    }
+
+    #[test]
+    fn locates_replaced_lines() {
+        let edit = ProposedEdit::Replace(vec![Replacement {
+            old: "b\nc\n".into(),
+            new: "x".into(),
+            all: false,
+        }]);
+        assert_eq!(
+            edit.line_ranges("a\nb\nc\nd\n"),
+            Some(vec![LineRange { start: 2, lines: 2 }])
+        );
+        assert_eq!(edit.line_ranges("a\n"), None);
+
+        let range = LineRange {
+            start: 10,
+            lines: 1,
+        };
+        assert!(range.near(
+            &LineRange {
+                start: 13,
+                lines: 2
+            },
+            3
+        ));
+        assert!(!range.near(
+            &LineRange {
+                start: 15,
+                lines: 2
+            },
+            3
+        ));
+    }
+}
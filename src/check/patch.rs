@@ -0,0 +1,120 @@
+//! Paths touched by patch-style edits
+//!
+//! Some agents edit many files at once through a single patch blob, either
+//! a unified diff (`git diff`, `diff -u`) or the `apply_patch` envelope:
+//!
+//! ```text
+//! *** Begin Patch
+//! *** Update File: src/lib.rs
+//! *** Move to: src/core.rs
+//! @@ fn main
+//! -old
+//! +new
+//! *** Delete File: src/old.rs
+//! *** End Patch
+//! ```
+//!
+//! Every added, modified, deleted or renamed path (both sides of a rename)
+//! is extracted so each can be checked like a normal `clash check` path.
+
+/// Paths a patch adds, modifies, deletes or renames, in order of appearance.
+///
+/// Unified diff paths have their `a/` and `b/` prefixes removed and are
+/// relative to wherever the patch applies (usually the repository root).
+pub fn touched_paths(patch: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    let mut push = |path: &str| {
+        let path = unquote(path.trim());
+        if !path.is_empty() && path != "/dev/null" && !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    };
+
+    let lines: Vec<&str> = patch.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let envelope = ["Add File:", "Update File:", "Delete File:", "Move to:"]
+            .iter()
+            .find_map(|marker| line.strip_prefix("*** ")?.strip_prefix(marker));
+        if let Some(path) = envelope {
+            push(path);
+        } else if let Some(rest) = line.strip_prefix("diff --git ") {
+            if let Some((old, new)) = rest.rsplit_once(" b/") {
+                push(old.strip_prefix("a/").unwrap_or(old));
+                push(new);
+            }
+        } else if let Some(path) = line
+            .strip_prefix("rename from ")
+            .or_else(|| line.strip_prefix("rename to "))
+            .or_else(|| line.strip_prefix("copy to "))
+        {
+            push(path);
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            // Only a `---`/`+++` pair is a file header; a lone `--- x` can be
+            // a removed line whose content starts with `--`
+            let Some(new) = lines.get(i + 1).and_then(|l| l.strip_prefix("+++ ")) else {
+                continue;
+            };
+            push(diff_header_path(old, "a/"));
+            push(diff_header_path(new, "b/"));
+        }
+    }
+    paths
+}
+
+/// Path from a `---`/`+++` header, without timestamp or `a/`/`b/` prefix
+fn diff_header_path<'a>(header: &'a str, prefix: &str) -> &'a str {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    let path = unquote(path);
+    path.strip_prefix(prefix).unwrap_or(path)
+}
+
+/// Strip the double quotes git puts around paths with unusual characters
+fn unquote(path: &str) -> &str {
+    path.strip_prefix('"')
+        .and_then(|p| p.strip_suffix('"'))
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_apply_patch_envelope() {
+        let patch = "*** Begin Patch\n\
+                     *** Add File: src/new.rs\n\
+                     +fn new() {}\n\
+                     *** Update File: src/lib.rs\n\
+                     *** Move to: src/core.rs\n\
+                     @@ fn main\n\
+                     --- not a header\n\
+                     +new\n\
+                     *** Delete File: src/old.rs\n\
+                     *** End Patch\n";
+        assert_eq!(
+            touched_paths(patch),
+            vec!["src/new.rs", "src/lib.rs", "src/core.rs", "src/old.rs"]
+        );
+    }
+
+    #[test]
+    fn parses_unified_diff() {
+        let patch = "diff --git a/src/a.rs b/src/a.rs\n\
+                     index 1111111..2222222 100644\n\
+                     --- a/src/a.rs\n\
+                     +++ b/src/a.rs\n\
+                     @@ -1 +1 @@\n\
+                     -old\n\
+                     +new\n\
+                     diff --git a/old name.rs b/new name.rs\n\
+                     similarity index 100%\n\
+                     rename from old name.rs\n\
+                     rename to new name.rs\n\
+                     --- /dev/null\t2024-01-01\n\
+                     +++ added.rs\t2024-01-01\n";
+        assert_eq!(
+            touched_paths(patch),
+            vec!["src/a.rs", "old name.rs", "new name.rs", "added.rs"]
+        );
+    }
+}
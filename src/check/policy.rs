@@ -0,0 +1,152 @@
+//! Hook decisions from the `.clash.toml` policy
+//!
+//! Every finding in a [`CheckOutput`] (one per conflicting worktree, claim
+//! or plan) is decided by the first matching policy rule, falling back to
+//! clash's defaults: claims deny, distant conflicts are allowed with a note
+//! to the agent, everything else asks. Ignored findings are removed from
+//! the output and the strictest remaining decision wins.
+
+use clash_sh::WorktreeManager;
+use clash_sh::config::{FindingKind, Policy, RuleDecision};
+use clash_sh::output::{CheckOutput, Decision};
+
+/// Decide a check result, removing findings the policy ignores.
+///
+/// Claims and plans only name a worktree, so its branch is looked up in
+/// `worktrees` for rules that match by branch.
+pub fn apply(
+    policy: &Policy,
+    worktrees: Option<&WorktreeManager>,
+    output: &mut CheckOutput,
+) -> Decision {
+    let path = output.file.clone();
+    let branch = |id: &str| {
+        worktrees
+            .and_then(|w| w.find(id).ok())
+            .map_or_else(|| id.to_string(), |wt| wt.branch.clone())
+    };
+    let mut decisions = Vec::new();
+
+    output.conflicts.retain(|c| {
+        let kinds = [
+            (c.has_merge_conflict, FindingKind::MergeConflict),
+            (
+                c.introduces_conflict == Some(true),
+                FindingKind::IntroducesConflict,
+            ),
+            (c.has_active_changes, FindingKind::ActiveChanges),
+        ];
+        let decision = kinds
+            .into_iter()
+            .filter(|(found, _)| *found)
+            .map(|(_, kind)| policy.decide(kind, &path, &c.worktree, &c.branch, RuleDecision::Ask))
+            .max()
+            .unwrap_or(RuleDecision::Ignore);
+        keep(decision, &mut decisions)
+    });
+    output.distant_conflicts.retain(|c| {
+        let decision = policy.decide(
+            FindingKind::DistantConflict,
+            &path,
+            &c.worktree,
+            &c.branch,
+            RuleDecision::Allow,
+        );
+        keep(decision, &mut decisions)
+    });
+    output.claimed_by.retain(|c| {
+        let decision = policy.decide(
+            FindingKind::Claimed,
+            &path,
+            &c.worktree,
+            &branch(&c.worktree),
+            RuleDecision::Deny,
+        );
+        keep(decision, &mut decisions)
+    });
+    output.planned_by.retain(|wt| {
+        let decision = policy.decide(
+            FindingKind::Planned,
+            &path,
+            wt,
+            &branch(wt),
+            RuleDecision::Ask,
+        );
+        keep(decision, &mut decisions)
+    });
+
+    decisions.into_iter().max().unwrap_or(Decision::Allow)
+}
+
+/// Record a finding's decision, returning whether the finding stays
+fn keep(decision: RuleDecision, decisions: &mut Vec<Decision>) -> bool {
+    let decision = match decision {
+        RuleDecision::Ignore => return false,
+        RuleDecision::Allow => Decision::Allow,
+        RuleDecision::Ask => Decision::Ask,
+        RuleDecision::Deny => Decision::Deny,
+    };
+    decisions.push(decision);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+    use clash_sh::config::Config;
+    use clash_sh::output::{ClaimInfo, SCHEMA_VERSION};
+
+    #[test]
+    fn claims_and_plans_match_rules_by_branch() {
+        let repo = TestRepo::new(&[("a.txt", "a\n")]);
+        let agent = repo.path("agent1");
+        repo.git(
+            "main",
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "feature/auth",
+                agent.to_str().unwrap(),
+            ],
+        );
+        let worktrees = repo.manager();
+        let policy = Config::parse(
+            r#"
+            [[policy.rules]]
+            worktrees = ["feature/auth"]
+            decision = "ignore"
+            "#,
+        )
+        .unwrap()
+        .policy;
+        let mut output = CheckOutput {
+            schema_version: SCHEMA_VERSION,
+            file: "a.txt".to_string(),
+            current_worktree: "main".to_string(),
+            current_branch: "main".to_string(),
+            conflicts: Vec::new(),
+            distant_conflicts: Vec::new(),
+            claimed_by: vec![ClaimInfo {
+                worktree: "agent1".to_string(),
+                session: None,
+                pattern: "*.txt".to_string(),
+            }],
+            planned_by: vec!["agent1".to_string()],
+            recent_edits: Vec::new(),
+            messages: Vec::new(),
+        };
+
+        // Without worktrees the branch is unknown and the default applies
+        let mut unresolved = output.clone();
+        assert_eq!(apply(&policy, None, &mut unresolved), Decision::Deny);
+
+        assert_eq!(
+            apply(&policy, Some(&worktrees), &mut output),
+            Decision::Allow
+        );
+        assert!(output.claimed_by.is_empty() && output.planned_by.is_empty());
+    }
+}
@@ -0,0 +1,632 @@
+//! Best-effort extraction of files a shell command will modify
+//!
+//! Agents can sidestep Write/Edit hooks by editing through their shell tool
+//! (`sed -i`, `cat > file`, `mv`, `git checkout -- path`, ...). This module
+//! tokenizes a command line the way a POSIX shell roughly would and picks out
+//! the paths that get written, moved or deleted, so each can be checked like
+//! a normal `clash check` path.
+//!
+//! It does not evaluate anything: words containing globs, variables or
+//! command substitutions are skipped, and unknown commands are assumed to
+//! only write through redirects.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Files that `command` writes, moves or deletes, in order of appearance.
+///
+/// Relative paths are returned relative to the directory the command starts
+/// in, `cwd`, following any literal `cd` along the way. After a `cd` whose
+/// destination isn't known (`cd -`, `cd $DIR`), only absolute paths are
+/// returned. `cwd` is only read to tell whether a copy's destination is an
+/// existing directory.
+pub fn write_targets(command: &str, cwd: &Path) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    // `None` once a `cd` went somewhere unknown
+    let mut dir = Some(PathBuf::new());
+
+    for cmd in split_commands(tokenize(command)) {
+        let mut found = cmd.redirects;
+        let words = strip_prefixes(&cmd.words);
+        if let Some((name, args)) = words.split_first() {
+            if name.text == "cd" {
+                dir = match args.first() {
+                    Some(arg) if arg.literal && arg.text != "-" => {
+                        dir.map(|dir| normalize(&dir.join(&arg.text))).or_else(|| {
+                            Path::new(&arg.text)
+                                .is_absolute()
+                                .then(|| normalize(Path::new(&arg.text)))
+                        })
+                    }
+                    _ => None,
+                };
+                continue;
+            }
+            let here = dir
+                .as_ref()
+                .map_or_else(|| cwd.to_path_buf(), |dir| cwd.join(dir));
+            found.extend(command_targets(name, args, &here));
+        }
+
+        for target in found {
+            if !target.literal
+                || target.text.is_empty()
+                || target.text == "-"
+                || target.text.starts_with("/dev/")
+            {
+                continue;
+            }
+            let path = match &dir {
+                Some(dir) => normalize(&dir.join(&target.text)),
+                None if Path::new(&target.text).is_absolute() => normalize(Path::new(&target.text)),
+                None => continue,
+            };
+            let path = path.to_string_lossy().into_owned();
+            if !path.is_empty() && !targets.contains(&path) {
+                targets.push(path);
+            }
+        }
+    }
+    targets
+}
+
+/// `path` with `.` and `..` resolved lexically, keeping leading `..`
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normal.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normal.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normal.push(".."),
+            },
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+// ============================================================================
+// Tokenizing
+// ============================================================================
+
+/// A shell word after quote removal
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    text: String,
+    /// False if the word contains an unquoted glob, a variable or a command
+    /// substitution, i.e. its value is only known at run time
+    literal: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(Word),
+    /// `;`, `&&`, `||`, `|`, `&` or a newline
+    Separator,
+    /// An output redirect (`>`, `>>`, `2>`, `&>`, ...); the next word is the target
+    WriteRedirect,
+    /// Any other redirect (`<`, `<<`, `2>&1`, ...); the next word is consumed
+    OtherRedirect,
+}
+
+fn tokenize(command: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = command.chars().peekable();
+    let mut word: Option<Word> = None;
+    let mut heredocs: Vec<String> = Vec::new();
+
+    fn finish(word: &mut Option<Word>, tokens: &mut Vec<Token>) {
+        if let Some(w) = word.take() {
+            tokens.push(Token::Word(w));
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_with(Word::empty);
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    w.text.push(c);
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(Word::empty);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(next) = chars.next() {
+                                w.text.push(next);
+                            }
+                        }
+                        '$' | '`' => {
+                            w.literal = false;
+                            w.text.push(c);
+                        }
+                        _ => w.text.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(next) = chars.next()
+                    && next != '\n'
+                {
+                    word.get_or_insert_with(Word::empty).text.push(next);
+                }
+            }
+            '#' if word.is_none() => while chars.next_if(|&c| c != '\n').is_some() {},
+            ' ' | '\t' => finish(&mut word, &mut tokens),
+            '\n' => {
+                finish(&mut word, &mut tokens);
+                tokens.push(Token::Separator);
+                // Heredoc bodies are data, not commands
+                for delimiter in heredocs.drain(..) {
+                    loop {
+                        let line: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                        if line.trim() == delimiter || chars.peek().is_none() {
+                            break;
+                        }
+                    }
+                }
+            }
+            ';' | '|' => {
+                finish(&mut word, &mut tokens);
+                chars.next_if(|&n| n == c || (c == '|' && n == '&'));
+                tokens.push(Token::Separator);
+            }
+            '&' if chars.peek() == Some(&'>') => {
+                finish(&mut word, &mut tokens);
+                chars.next();
+                chars.next_if_eq(&'>');
+                tokens.push(Token::WriteRedirect);
+            }
+            '&' => {
+                finish(&mut word, &mut tokens);
+                chars.next_if_eq(&'&');
+                tokens.push(Token::Separator);
+            }
+            '>' | '<' => {
+                // A word made only of digits before the operator is a file descriptor
+                if word
+                    .as_ref()
+                    .is_some_and(|w| w.literal && w.text.chars().all(|c| c.is_ascii_digit()))
+                {
+                    word = None;
+                }
+                finish(&mut word, &mut tokens);
+
+                if c == '<' {
+                    let heredoc = chars.next_if_eq(&'<').is_some();
+                    if heredoc && chars.next_if_eq(&'<').is_none() {
+                        chars.next_if_eq(&'-');
+                        while chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+                        let mut delimiter = String::new();
+                        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                            if c != '\'' && c != '"' {
+                                delimiter.push(c);
+                            }
+                        }
+                        heredocs.push(delimiter);
+                        continue;
+                    }
+                    chars.next_if_eq(&'>');
+                    tokens.push(Token::OtherRedirect);
+                } else if chars.next_if_eq(&'&').is_some() {
+                    // `>&2` duplicates a descriptor, `>&file` is bash for `&>file`
+                    if chars
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_digit() || *c == '-')
+                    {
+                        tokens.push(Token::OtherRedirect);
+                    } else {
+                        tokens.push(Token::WriteRedirect);
+                    }
+                } else {
+                    chars.next_if(|&n| n == '>' || n == '|');
+                    tokens.push(Token::WriteRedirect);
+                }
+            }
+            '(' | ')' | '{' | '}' if word.is_none() => tokens.push(Token::Separator),
+            '~' if word.is_none() => {
+                word = Some(Word {
+                    text: c.to_string(),
+                    literal: false,
+                })
+            }
+            '$' | '`' | '*' | '?' | '[' => {
+                let w = word.get_or_insert_with(Word::empty);
+                w.literal = false;
+                w.text.push(c);
+            }
+            _ => word.get_or_insert_with(Word::empty).text.push(c),
+        }
+    }
+    finish(&mut word, &mut tokens);
+    tokens
+}
+
+impl Word {
+    fn empty() -> Self {
+        Word {
+            text: String::new(),
+            literal: true,
+        }
+    }
+}
+
+// ============================================================================
+// Simple commands
+// ============================================================================
+
+/// One simple command: its words and the targets of its output redirects
+#[derive(Debug, Default)]
+struct SimpleCommand {
+    words: Vec<Word>,
+    redirects: Vec<Word>,
+}
+
+fn split_commands(tokens: Vec<Token>) -> Vec<SimpleCommand> {
+    let mut commands = vec![SimpleCommand::default()];
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        let current = commands.last_mut().expect("never empty");
+        match token {
+            Token::Word(w) => current.words.push(w),
+            Token::WriteRedirect => {
+                if let Some(Token::Word(w)) = tokens.next() {
+                    current.redirects.push(w);
+                }
+            }
+            Token::OtherRedirect => {
+                tokens.next();
+            }
+            Token::Separator => commands.push(SimpleCommand::default()),
+        }
+    }
+    commands
+}
+
+/// Skip variable assignments and wrappers like `sudo` that run the next word
+fn strip_prefixes(words: &[Word]) -> &[Word] {
+    let mut words = words;
+    while let Some((first, rest)) = words.split_first() {
+        let is_assignment = first
+            .text
+            .split_once('=')
+            .is_some_and(|(name, _)| !name.is_empty() && !name.contains('/'));
+        let is_wrapper = matches!(
+            first.text.as_str(),
+            "sudo" | "env" | "command" | "nohup" | "time" | "exec" | "then" | "do" | "else"
+        );
+        if !is_assignment && !is_wrapper {
+            break;
+        }
+        words = rest;
+    }
+    words
+}
+
+/// Non-option arguments, skipping the values of options in `with_value`
+fn operands<'a>(args: &'a [Word], with_value: &[&str]) -> Vec<&'a Word> {
+    let mut result = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.text == "--" {
+            result.extend(args);
+            break;
+        }
+        if arg.text.starts_with('-') && arg.text.len() > 1 {
+            if with_value.contains(&arg.text.as_str()) {
+                args.next();
+            }
+            continue;
+        }
+        result.push(arg);
+    }
+    result
+}
+
+/// Files a known command, run in `cwd`, modifies through its arguments
+fn command_targets(name: &Word, args: &[Word], cwd: &Path) -> Vec<Word> {
+    let program = Path::new(&name.text)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let targets: Vec<&Word> = match program.as_str() {
+        "rm" | "unlink" | "shred" => operands(args, &[]),
+        "touch" => operands(args, &["-d", "-r", "-t", "--date", "--reference"]),
+        // The sources go away and reappear under the destination
+        "mv" => {
+            let mut moved = copy_targets(args, cwd);
+            let mut sources = operands(args, &["-S", "-t", "--suffix", "--target-directory"]);
+            if target_directory(args).is_none() {
+                sources.pop();
+            }
+            moved.splice(0..0, sources.into_iter().cloned());
+            return moved;
+        }
+        "tee" => operands(args, &[]),
+        "truncate" => operands(args, &["-s", "-r", "--size", "--reference"]),
+        "cp" | "install" | "ln" => return copy_targets(args, cwd),
+        "sed" => sed_targets(args),
+        "dd" => {
+            return args
+                .iter()
+                .filter_map(|a| {
+                    Some(Word {
+                        text: a.text.strip_prefix("of=")?.to_string(),
+                        literal: a.literal,
+                    })
+                })
+                .collect();
+        }
+        "git" => return git_targets(args),
+        _ => Vec::new(),
+    };
+
+    targets.into_iter().cloned().collect()
+}
+
+/// Files created by `cp`, `install` or `ln`: the destination, or each
+/// source's name inside it when the destination is a directory
+fn copy_targets(args: &[Word], cwd: &Path) -> Vec<Word> {
+    let target_dir = target_directory(args);
+    let mut files = operands(
+        args,
+        &[
+            "-m",
+            "-o",
+            "-g",
+            "-S",
+            "-t",
+            "--suffix",
+            "--target-directory",
+        ],
+    );
+    let Some(dest) = target_dir.as_ref().or_else(|| files.pop()) else {
+        return Vec::new();
+    };
+
+    let no_target_dir = args
+        .iter()
+        .any(|a| a.text == "-T" || a.text == "--no-target-directory");
+    let into_dir = target_dir.is_some()
+        || (!no_target_dir
+            && (dest.text.ends_with('/') || (dest.literal && cwd.join(&dest.text).is_dir())));
+    if !into_dir || files.is_empty() {
+        return vec![dest.clone()];
+    }
+    files
+        .into_iter()
+        .filter_map(|source| {
+            let name = Path::new(&source.text).file_name()?;
+            Some(Word {
+                text: Path::new(&dest.text)
+                    .join(name)
+                    .to_string_lossy()
+                    .into_owned(),
+                literal: dest.literal && source.literal,
+            })
+        })
+        .collect()
+}
+
+/// The directory given with `-t DIR`, `--target-directory DIR` or
+/// `--target-directory=DIR`
+fn target_directory(args: &[Word]) -> Option<Word> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if let Some(dir) = arg.text.strip_prefix("--target-directory=") {
+            return Some(Word {
+                text: dir.to_string(),
+                literal: arg.literal,
+            });
+        }
+        (arg.text == "-t" || arg.text == "--target-directory")
+            .then(|| args.get(i + 1).cloned())
+            .flatten()
+    })
+}
+
+/// Files edited by `sed -i`; plain `sed` only writes to stdout
+fn sed_targets(args: &[Word]) -> Vec<&Word> {
+    let in_place = args.iter().any(|a| {
+        a.text.starts_with("--in-place")
+            || (a.text.starts_with('-')
+                && !a.text.starts_with("--")
+                && a.text[1..]
+                    .split('.')
+                    .next()
+                    .is_some_and(|f| f.contains('i')))
+    });
+    if !in_place {
+        return Vec::new();
+    }
+
+    let has_script_option = args
+        .iter()
+        .any(|a| matches!(a.text.as_str(), "-e" | "-f") || a.text.starts_with("--expression"));
+    // BSD `sed -i ''` passes an empty backup suffix as its own word
+    let mut files = operands(args, &["-e", "-f", "-l", "--expression", "--file"]);
+    files.retain(|w| !w.text.is_empty());
+    if !has_script_option && !files.is_empty() {
+        // The first operand is the script
+        files.remove(0);
+    }
+    files
+}
+
+/// Files changed by `git checkout -- <paths>`, `git restore`, `git rm` and `git mv`
+fn git_targets(args: &[Word]) -> Vec<Word> {
+    let mut args = args;
+    let mut dir = PathBuf::new();
+    // Global options before the subcommand
+    while let Some((first, rest)) = args.split_first() {
+        match first.text.as_str() {
+            "-C" => {
+                if let Some(d) = rest.first() {
+                    dir.push(&d.text);
+                }
+                args = rest.get(1..).unwrap_or_default();
+            }
+            "-c" | "--git-dir" | "--work-tree" => args = rest.get(1..).unwrap_or_default(),
+            s if s.starts_with('-') => args = rest,
+            _ => break,
+        }
+    }
+
+    let Some((sub, args)) = args.split_first() else {
+        return Vec::new();
+    };
+    let targets: Vec<&Word> = match sub.text.as_str() {
+        // Without `--`, checkout's operands are usually a branch, not paths
+        "checkout" => match args.iter().position(|a| a.text == "--") {
+            Some(i) => args[i + 1..].iter().collect(),
+            None => Vec::new(),
+        },
+        // `--staged` alone only touches the index
+        "restore" if has_flag(args, "--staged", 'S') && !has_flag(args, "--worktree", 'W') => {
+            Vec::new()
+        }
+        "restore" => operands(args, &["-s", "--source"]),
+        "rm" | "mv" => operands(args, &[]),
+        _ => Vec::new(),
+    };
+
+    targets
+        .into_iter()
+        .map(|w| Word {
+            text: dir.join(&w.text).to_string_lossy().into_owned(),
+            literal: w.literal,
+        })
+        .collect()
+}
+
+/// Whether `args` has the long option or the short flag, alone or bundled
+fn has_flag(args: &[Word], long: &str, short: char) -> bool {
+    args.iter().take_while(|a| a.text != "--").any(|a| {
+        a.text == long
+            || (a.text.starts_with('-') && !a.text.starts_with("--") && a.text.contains(short))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_redirects_and_file_commands() {
+        assert_eq!(
+            write_targets(
+                "cat > 'a b.txt' <<'EOF'\nrm -rf nothing\nEOF\necho hi >> log.txt 2>&1",
+                Path::new(".")
+            ),
+            vec!["a b.txt", "log.txt"]
+        );
+        assert_eq!(
+            write_targets(
+                "sed -i.bak 's/a/b/' src/x.rs && sed -n 1p src/y.rs",
+                Path::new(".")
+            ),
+            vec!["src/x.rs"]
+        );
+        assert_eq!(
+            write_targets(
+                "mv old.rs new.rs; cp -r a.rs dest/ | tee -a out.log",
+                Path::new(".")
+            ),
+            vec!["old.rs", "new.rs", "dest/a.rs", "out.log"]
+        );
+        assert_eq!(
+            write_targets(
+                "cd src && git checkout -- lib.rs && git restore -SW main.rs",
+                Path::new(".")
+            ),
+            vec!["src/lib.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            write_targets(
+                "sudo rm -f *.tmp $OUT /dev/null; echo x > /dev/null",
+                Path::new(".")
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn ignores_read_only_commands() {
+        assert!(
+            write_targets(
+                "grep -rn 'a > b' src | head -5 && cat x 2>/dev/null",
+                Path::new(".")
+            )
+            .is_empty()
+        );
+        assert!(write_targets("git checkout main && ls > /dev/stderr", Path::new(".")).is_empty());
+        assert!(write_targets("git restore --staged src/main.rs", Path::new(".")).is_empty());
+    }
+
+    #[test]
+    fn skips_option_values_and_follows_cd() {
+        let cwd = Path::new(".");
+        assert_eq!(
+            write_targets(
+                "touch -d tomorrow a.rs; touch -r ref.rs -t 202401010000 b.rs",
+                cwd
+            ),
+            vec!["a.rs", "b.rs"]
+        );
+        assert_eq!(
+            write_targets(
+                "mv -t dest a.rs sub/b.rs; mv --target-directory=d c.rs",
+                cwd
+            ),
+            vec![
+                "a.rs",
+                "sub/b.rs",
+                "dest/a.rs",
+                "dest/b.rs",
+                "c.rs",
+                "d/c.rs"
+            ]
+        );
+        assert_eq!(
+            write_targets(
+                "cd src/x && touch ../a.rs && cd ../.. && cd .. && rm b.rs",
+                cwd
+            ),
+            vec!["src/a.rs", "../b.rs"]
+        );
+        assert_eq!(
+            write_targets("cd src && cd - && touch a.rs /abs/b.rs", cwd),
+            vec!["/abs/b.rs"]
+        );
+    }
+
+    #[test]
+    fn copies_into_directories_name_each_source() {
+        let cwd = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(cwd.path().join("sub/dest")).unwrap();
+        assert_eq!(
+            write_targets("cd sub && cp a.rs lib/b.rs dest", cwd.path()),
+            vec!["sub/dest/a.rs", "sub/dest/b.rs"]
+        );
+        assert_eq!(
+            write_targets(
+                "ln -s ../a.rs sub/dest; install -m 644 a.rs new.rs",
+                cwd.path()
+            ),
+            vec!["sub/dest/a.rs", "new.rs"]
+        );
+        assert_eq!(
+            write_targets("cp -t out a.rs b.rs && cp -T a.rs sub/dest", cwd.path()),
+            vec!["out/a.rs", "out/b.rs", "sub/dest"]
+        );
+    }
+}
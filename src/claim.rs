@@ -0,0 +1,110 @@
+use clash_sh::WorktreeManager;
+use clash_sh::registry::{Claim, Registry, validate_pattern};
+use colored::Colorize;
+
+/// Claim glob patterns for the current worktree, or list all claims if none are given.
+pub fn run_claim(
+    worktrees: &WorktreeManager,
+    patterns: &[String],
+    session: Option<&str>,
+) -> Result<(), String> {
+    let registry = Registry::open(worktrees);
+
+    if patterns.is_empty() {
+        return list_claims(&registry);
+    }
+
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+
+    for pattern in patterns {
+        validate_pattern(pattern).map_err(|e| format!("invalid pattern '{}': {}", pattern, e))?;
+    }
+
+    // Warn (but still claim) when someone else holds an overlapping pattern
+    let existing = registry.claims().map_err(|e| e.to_string())?;
+    for pattern in patterns {
+        for other in existing
+            .iter()
+            .filter(|c| c.overlaps(pattern) && !c.is_held_by(&current.id, session))
+        {
+            let held = if other.pattern == pattern.trim_start_matches("./") {
+                String::new()
+            } else {
+                format!(" as {}", other.pattern)
+            };
+            eprintln!(
+                "{} {} is already claimed by {}{}",
+                "Warning:".bright_yellow().bold(),
+                pattern,
+                other.worktree.bright_blue(),
+                held
+            );
+        }
+    }
+
+    let claims = patterns
+        .iter()
+        .map(|p| Claim::new(&current.id, session, p))
+        .collect();
+    registry.add_claims(claims).map_err(|e| e.to_string())?;
+
+    for pattern in patterns {
+        println!(
+            "{} {} for {}",
+            "Claimed".bright_green().bold(),
+            pattern.yellow(),
+            current.id.bright_blue()
+        );
+    }
+    Ok(())
+}
+
+/// Release claims held by the current worktree (all of them if no patterns are given).
+pub fn run_release(
+    worktrees: &WorktreeManager,
+    patterns: &[String],
+    session: Option<&str>,
+) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+
+    let released = Registry::open(worktrees)
+        .release_claims(&current.id, session, patterns)
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Released {} claim{} for {}",
+        released.to_string().bright_green().bold(),
+        if released == 1 { "" } else { "s" },
+        current.id.bright_blue()
+    );
+    Ok(())
+}
+
+/// Print all active claims
+fn list_claims(registry: &Registry) -> Result<(), String> {
+    let claims = registry.claims().map_err(|e| e.to_string())?;
+    if claims.is_empty() {
+        println!("No active claims");
+        return Ok(());
+    }
+
+    println!("{}", "Claims:".bright_cyan().bold());
+    for claim in &claims {
+        let session = claim
+            .session
+            .as_deref()
+            .map(|s| format!(" (session {})", s))
+            .unwrap_or_default();
+        println!(
+            "  {}: {}{}",
+            claim.worktree.bright_blue(),
+            claim.pattern.yellow(),
+            session.bright_black()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,250 @@
+//! Repository configuration from `.clash.toml`
+//!
+//! An optional TOML file at the main worktree root, shared by every
+//! worktree like `.clashignore`. It holds the hook [`Policy`]: rules that
+//! decide whether an agent's edit is allowed, needs confirmation or is
+//! refused, depending on what `clash check` found. It also sets whether the
+//! git hooks installed by `clash hooks install` only warn or block.
+//!
+//! ```toml
+//! [policy]
+//! on_error = "deny"            # fail closed when the check itself fails
+//!
+//! [[policy.rules]]
+//! when = ["merge_conflict", "introduces_conflict"]
+//! paths = ["src/auth/**"]
+//! decision = "deny"
+//!
+//! [[policy.rules]]
+//! paths = ["**/*.generated.rs"]
+//! decision = "ignore"
+//!
+//! [git_hooks]
+//! mode = "block"               # refuse commits and pushes that conflict
+//! ```
+
+use crate::WorktreeManager;
+use crate::registry::{glob_matches, validate_pattern};
+use serde::Deserialize;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Name of the config file at the main worktree root
+pub const CONFIG_FILE: &str = ".clash.toml";
+
+/// Errors reading `.clash.toml`
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid {path}: {message}")]
+    Invalid { path: PathBuf, message: String },
+}
+
+/// Contents of `.clash.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub policy: Policy,
+    #[serde(default)]
+    pub git_hooks: GitHooks,
+}
+
+/// Behaviour of the pre-commit and pre-push checks
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GitHooks {
+    #[serde(default)]
+    pub mode: GitHookMode,
+}
+
+/// What a git hook does when it finds conflicts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitHookMode {
+    /// Print the conflicts and let the commit or push go ahead
+    #[default]
+    Warn,
+    /// Print the conflicts and abort the commit or push
+    Block,
+}
+
+/// How hook mode turns findings into a decision
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Decision when the check itself fails (`allow` fails open). Files
+    /// outside every worktree always pass, and an unreadable config asks.
+    #[serde(default)]
+    pub on_error: ErrorDecision,
+    /// Rules tried in order; the first one matching a finding decides it
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// A condition → decision rule
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Kinds of finding the rule applies to (any kind if empty)
+    #[serde(default)]
+    pub when: Vec<FindingKind>,
+    /// Glob patterns for the repo-relative path (any path if empty)
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Ids or branches of the other worktree (any worktree if empty)
+    #[serde(default)]
+    pub worktrees: Vec<String>,
+    pub decision: RuleDecision,
+}
+
+/// What `clash check` found for one other worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The file conflicts when the two branches are merged
+    MergeConflict,
+    /// The proposed edit would create a merge conflict
+    IntroducesConflict,
+    /// The other worktree has uncommitted changes to the file
+    ActiveChanges,
+    /// The other worktree's changes are far from the lines being edited
+    DistantConflict,
+    /// The other worktree claimed the file
+    Claimed,
+    /// The other worktree plans to edit the file
+    Planned,
+}
+
+/// Decision a rule assigns to a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleDecision {
+    /// Drop the finding entirely
+    Ignore,
+    /// Let the edit through, telling the agent about the finding
+    Allow,
+    /// Ask the user to confirm the edit
+    Ask,
+    /// Refuse the edit
+    Deny,
+}
+
+/// Decision when the check fails before producing findings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorDecision {
+    /// Report the error and let the edit through (fail open)
+    #[default]
+    Allow,
+    /// Ask the user to confirm the edit
+    Ask,
+    /// Refuse the edit (fail closed)
+    Deny,
+}
+
+impl Config {
+    /// Load `.clash.toml` from the main worktree root.
+    ///
+    /// A missing file gives the default config. Unlike `.clashignore`, a
+    /// malformed file is an error: silently dropping a `deny` rule would
+    /// weaken the policy without anyone noticing.
+    pub fn load(worktrees: &WorktreeManager) -> Result<Self, ConfigError> {
+        let Some(main) = worktrees.main() else {
+            return Ok(Self::default());
+        };
+        let path = main.path.join(CONFIG_FILE);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => return Err(ConfigError::Io { path, source }),
+        };
+        Self::parse(&text).map_err(|message| ConfigError::Invalid { path, message })
+    }
+
+    /// Parse and validate config text
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(text).map_err(|e| e.to_string())?;
+        for pattern in config.policy.rules.iter().flat_map(|r| &r.paths) {
+            validate_pattern(pattern).map_err(|e| format!("pattern '{}': {}", pattern, e))?;
+        }
+        Ok(config)
+    }
+}
+
+impl Rule {
+    /// Check whether this rule applies to a finding of `kind` on `path`
+    /// involving the worktree `worktree` (id) on `branch`
+    pub fn matches(&self, kind: FindingKind, path: &str, worktree: &str, branch: &str) -> bool {
+        (self.when.is_empty() || self.when.contains(&kind))
+            && (self.paths.is_empty() || self.paths.iter().any(|p| glob_matches(p, path)))
+            && (self.worktrees.is_empty()
+                || self.worktrees.iter().any(|w| w == worktree || w == branch))
+    }
+}
+
+impl Policy {
+    /// Decide a finding: the first matching rule wins, otherwise `default`
+    pub fn decide(
+        &self,
+        kind: FindingKind,
+        path: &str,
+        worktree: &str,
+        branch: &str,
+        default: RuleDecision,
+    ) -> RuleDecision {
+        self.rules
+            .iter()
+            .find(|r| r.matches(kind, path, worktree, branch))
+            .map_or(default, |r| r.decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let config = Config::parse(
+            r#"
+            [policy]
+            on_error = "deny"
+
+            [[policy.rules]]
+            when = ["merge_conflict"]
+            paths = ["src/auth/**"]
+            decision = "deny"
+
+            [[policy.rules]]
+            paths = ["**/*.gen.rs"]
+            decision = "ignore"
+            "#,
+        )
+        .unwrap();
+        let policy = &config.policy;
+        let decide = |kind, path| policy.decide(kind, path, "wt", "b", RuleDecision::Ask);
+
+        assert_eq!(policy.on_error, ErrorDecision::Deny);
+        assert_eq!(
+            decide(FindingKind::MergeConflict, "src/auth/login.rs"),
+            RuleDecision::Deny
+        );
+        assert_eq!(
+            decide(FindingKind::ActiveChanges, "src/auth/login.rs"),
+            RuleDecision::Ask
+        );
+        assert_eq!(
+            decide(FindingKind::MergeConflict, "src/api.gen.rs"),
+            RuleDecision::Ignore
+        );
+
+        assert!(Config::parse("[[policy.rules]]\npaths = ['[']\ndecision = 'deny'").is_err());
+        assert!(Config::parse("[policy]\nunknown = 1").is_err());
+    }
+}
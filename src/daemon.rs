@@ -0,0 +1,342 @@
+//! Background daemon answering queries over a Unix domain socket
+//!
+//! `clash daemon` keeps a [`LiveState`] warm and listens on
+//! `<common git dir>/clash/daemon.sock`. `clash check` and `clash status`
+//! try the socket first and compute results directly when no daemon is
+//! listening, so running the daemon only ever makes them faster.
+//!
+//! The protocol is newline-delimited JSON. Each request is an object with a
+//! `method` (`ping`, `pairs` or `check`); each response is a [`DaemonReply`]
+//! with `ok` or `error`. Replies from a daemon of another schema version
+//! (an older clash still running) are ignored, like no daemon at all.
+//!
+//! Results are only used while they're current: `pairs` comes with the
+//! worktrees' HEADs it was computed from, which the client compares with
+//! its own, and `check` is refused while a HEAD has moved since the last
+//! refresh.
+//!
+//! [`DaemonReply`]: clash_sh::output::DaemonReply
+
+use crate::check::Proposal;
+use crate::live::head_ids;
+use clash_sh::output::CheckOutput;
+use clash_sh::{Worktree, WorktreeManager, WorktreePairConflict};
+#[cfg(unix)]
+use serde_json::Value;
+use serde_json::json;
+
+// ============================================================================
+// Client
+// ============================================================================
+
+/// Fetch pair results from the daemon serving this repository.
+///
+/// Returns `None` when no daemon is running, or when its results don't
+/// cover exactly the worktrees, branches and HEADs the caller discovered,
+/// e.g. because a worktree was added or committed to within the daemon's
+/// debounce window.
+pub fn cached_pairs(worktrees: &WorktreeManager) -> Option<Vec<WorktreePairConflict>> {
+    let response = unix::request(worktrees.common_dir(), &json!({ "method": "pairs" }))?;
+    let cached: CachedPairs = serde_json::from_value(response).ok()?;
+    covers(&cached, worktrees).then_some(cached.pairs)
+}
+
+/// Answer to a `pairs` request
+#[derive(Debug, serde::Deserialize)]
+struct CachedPairs {
+    /// Each worktree's HEAD when the pairs were computed
+    heads: Vec<(String, Option<String>)>,
+    pairs: Vec<WorktreePairConflict>,
+}
+
+/// Whether `cached` has one pair per pair of `worktrees`, computed on the
+/// same branches and HEADs
+fn covers(cached: &CachedPairs, worktrees: &WorktreeManager) -> bool {
+    let pairs = &cached.pairs;
+    let known = |wt: &Worktree| {
+        worktrees
+            .iter()
+            .any(|w| w.id == wt.id && w.branch == wt.branch)
+    };
+    let n = worktrees.len();
+    pairs.len() == n * n.saturating_sub(1) / 2
+        && pairs.iter().all(|p| known(&p.wt1) && known(&p.wt2))
+        && cached.heads == head_ids(worktrees)
+}
+
+/// Ask the daemon serving the repository containing `path` to check a file.
+///
+/// Returns `None` when no daemon is running or it couldn't answer, so the
+/// caller can fall back to checking directly (and report errors itself).
+pub fn check(
+    path: &str,
+    session: Option<&str>,
+    all: bool,
+    proposed: Option<&Proposal>,
+) -> Option<CheckOutput> {
+    // The daemon resolves relative paths against its own cwd, not ours
+    let abs_path = std::path::absolute(path).ok()?;
+    let common_dir = WorktreeManager::discover_common_dir(path).ok()?;
+    let request = json!({
+        "method": "check",
+        "path": abs_path,
+        "session": session,
+        "all": all,
+        "proposed": proposed,
+    });
+    serde_json::from_value(unix::request(&common_dir, &request)?).ok()
+}
+
+// ============================================================================
+// Server
+// ============================================================================
+
+/// A request received on the daemon socket
+#[cfg(unix)]
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    /// Liveness check, answered with the snapshot generation
+    Ping,
+    /// Unsuppressed results for every worktree pair
+    Pairs,
+    /// Same as `clash check <path>`
+    Check {
+        path: String,
+        session: Option<String>,
+        #[serde(default)]
+        all: bool,
+        /// An edit the caller is about to make to the file
+        #[serde(default)]
+        proposed: Option<Proposal>,
+    },
+}
+
+/// Answer one request from the current snapshot
+#[cfg(unix)]
+fn handle_request(request: Request, live: &crate::live::LiveState) -> Result<Value, String> {
+    let snapshot = live.snapshot();
+    match request {
+        Request::Ping => Ok(json!({ "generation": snapshot.generation })),
+        // Shaped like `CachedPairs`
+        Request::Pairs => Ok(json!({ "heads": snapshot.heads, "pairs": snapshot.pairs })),
+        Request::Check {
+            path,
+            session,
+            all,
+            proposed,
+        } => {
+            // The caller falls back to checking directly
+            if head_ids(&snapshot.worktrees) != snapshot.heads {
+                return Err("results are being refreshed after a commit".to_string());
+            }
+            let suppressions = if all {
+                clash_sh::Suppressions::none()
+            } else {
+                clash_sh::Suppressions::load(&snapshot.worktrees)
+            };
+            let output = crate::check::check_file(
+                &snapshot.worktrees,
+                &path,
+                session.as_deref(),
+                &suppressions,
+                Some(&snapshot.pairs),
+                proposed.as_ref(),
+            )
+            .map_err(|e| e.to_string())?;
+            serde_json::to_value(&output).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Run the daemon in the foreground until killed
+pub fn run_daemon(worktrees: WorktreeManager) -> Result<(), String> {
+    unix::serve(worktrees)
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{Request, handle_request};
+    use crate::live::LiveState;
+    use clash_sh::WorktreeManager;
+    use clash_sh::output::{DaemonReply, SCHEMA_VERSION};
+    use clash_sh::registry::Registry;
+    use serde_json::Value;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Socket file inside the registry directory
+    const SOCKET_FILE: &str = "daemon.sock";
+
+    /// Give up on an unresponsive daemon after this long and compute directly
+    const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Path of the daemon socket for a repository
+    fn socket_path(common_dir: &Path) -> PathBuf {
+        Registry::for_common_dir(common_dir).dir().join(SOCKET_FILE)
+    }
+
+    /// Send one request and return the `ok` payload, or `None` on any failure
+    /// (including a reply of another schema version)
+    pub fn request(common_dir: &Path, request: &Value) -> Option<Value> {
+        let stream = UnixStream::connect(socket_path(common_dir)).ok()?;
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+        writeln!(&stream, "{}", request).ok()?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).ok()?;
+        let reply: DaemonReply = serde_json::from_str(&line).ok()?;
+        if reply.schema_version != SCHEMA_VERSION {
+            return None;
+        }
+        reply.ok
+    }
+
+    pub fn serve(worktrees: WorktreeManager) -> Result<(), String> {
+        let path = socket_path(worktrees.common_dir());
+
+        // A leftover socket from a daemon that was killed is safe to replace,
+        // but one that still accepts connections belongs to a live daemon
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(format!(
+                    "a daemon is already listening on {}",
+                    path.display()
+                ));
+            }
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("failed to remove stale socket: {}", e))?;
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        }
+
+        let live = LiveState::start(worktrees).map_err(|e| e.to_string())?;
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| format!("failed to bind {}: {}", path.display(), e))?;
+        println!("clash daemon listening on {}", path.display());
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let live = live.clone();
+                    thread::spawn(move || serve_client(stream, &live));
+                }
+                Err(e) => eprintln!("Connection error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer requests on one connection until the client disconnects
+    fn serve_client(stream: UnixStream, live: &LiveState) {
+        let reader = BufReader::new(&stream);
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let result = serde_json::from_str::<Request>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(|request| handle_request(request, live));
+            let response = serde_json::to_string(&DaemonReply::new(result)).unwrap_or_default();
+            if writeln!(&stream, "{}", response).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod unix {
+    use clash_sh::WorktreeManager;
+    use serde_json::Value;
+    use std::path::Path;
+
+    /// Without Unix domain sockets there is never a daemon to ask
+    pub fn request(_common_dir: &Path, _request: &Value) -> Option<Value> {
+        None
+    }
+
+    pub fn serve(_worktrees: WorktreeManager) -> Result<(), String> {
+        Err("clash daemon requires Unix domain sockets".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    #[test]
+    fn pairs_from_before_a_worktree_change_are_stale() {
+        let repo = TestRepo::new(&[("a.txt", "base\n")]);
+        repo.add_worktree("feature");
+        let before = repo.manager();
+        let heads = head_ids(&before);
+        let cached = |pairs| CachedPairs {
+            heads: heads.clone(),
+            pairs,
+        };
+        let pairs = before.check_all_conflicts();
+        assert!(covers(&cached(pairs.clone()), &before));
+        // No daemon is listening, so callers compute the pairs themselves
+        assert!(cached_pairs(&before).is_none());
+
+        let mut renamed = pairs.clone();
+        renamed[0].wt2.branch = "renamed".to_string();
+        assert!(!covers(&cached(renamed), &before));
+
+        repo.commit("feature", "same branch, new HEAD");
+        assert!(!covers(&cached(pairs.clone()), &repo.manager()));
+
+        repo.add_worktree("other");
+        assert!(!covers(&cached(pairs), &repo.manager()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn requests_round_trip_through_the_socket() {
+        let repo = TestRepo::new(&[("a.txt", "base\n")]);
+        repo.add_worktree("feature");
+        repo.write("feature", "a.txt", "theirs\n");
+        repo.commit("feature", "theirs");
+        repo.write("main", "a.txt", "ours\n");
+        repo.commit("main", "ours");
+
+        let daemon = repo.manager();
+        std::thread::spawn(move || run_daemon(daemon));
+        let worktrees = repo.manager();
+        let common_dir = worktrees.common_dir().to_path_buf();
+        let ping = json!({ "method": "ping" });
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let reply = loop {
+            if let Some(reply) = unix::request(&common_dir, &ping) {
+                break reply;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "daemon never answered"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        };
+        assert!(reply["generation"].is_u64());
+
+        let pairs = cached_pairs(&worktrees).expect("daemon pairs");
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].conflicting_files, vec!["a.txt"]);
+
+        let path = repo.path("main").join("a.txt");
+        let output = check(path.to_str().unwrap(), None, false, None).expect("daemon check");
+        assert!(output.has_conflicts());
+
+        // Errors come back as `error`, which the client treats as no answer
+        assert!(unix::request(&common_dir, &json!({ "method": "nope" })).is_none());
+    }
+}
@@ -0,0 +1,366 @@
+//! CI output formats for `status` and `check`
+//!
+//! Both commands turn their findings into [`Annotation`]s (one per
+//! conflicting region of a file) and render them as SARIF for code-scanning
+//! UIs, JUnit XML for test-report viewers, or GitHub Actions workflow
+//! commands that annotate the pull request diff.
+
+use clash_sh::PairFileDiff;
+use serde_json::json;
+use std::fmt::Write;
+
+/// Output format of `status` and `check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// Clash's own JSON document
+    Json,
+    /// SARIF 2.1.0, for code-scanning UIs
+    Sarif,
+    /// JUnit XML, for test-report viewers
+    Junit,
+    /// GitHub Actions workflow commands (`::error file=...::`)
+    Gha,
+}
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Notice,
+}
+
+/// The kind of finding, reported as the SARIF rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Merging the two worktrees conflicts on the file
+    MergeConflict,
+    /// Another worktree has uncommitted changes to the file
+    ActiveChanges,
+    /// Another worktree changed a different part of the file
+    DistantConflict,
+    /// Another worktree claimed the file
+    Claimed,
+    /// Another worktree plans to edit the file
+    Planned,
+    /// The check itself failed for a worktree pair
+    CheckFailed,
+}
+
+/// Every rule, in the order SARIF lists them
+const RULES: &[Rule] = &[
+    Rule::MergeConflict,
+    Rule::ActiveChanges,
+    Rule::DistantConflict,
+    Rule::Claimed,
+    Rule::Planned,
+    Rule::CheckFailed,
+];
+
+impl Rule {
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::MergeConflict => "merge-conflict",
+            Rule::ActiveChanges => "active-changes",
+            Rule::DistantConflict => "distant-conflict",
+            Rule::Claimed => "claimed",
+            Rule::Planned => "planned",
+            Rule::CheckFailed => "check-failed",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Rule::MergeConflict => "The file conflicts when merged with another worktree",
+            Rule::ActiveChanges => "Another worktree has uncommitted changes to the file",
+            Rule::DistantConflict => "Another worktree changed a different part of the file",
+            Rule::Claimed => "Another worktree claimed the file",
+            Rule::Planned => "Another worktree plans to edit the file",
+            Rule::CheckFailed => "Conflicts could not be checked for a worktree pair",
+        }
+    }
+}
+
+/// One finding, located in a file when it has a location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Group the finding belongs to: the worktree pair or the checked file
+    pub suite: String,
+    pub rule: Rule,
+    pub level: Level,
+    /// Repository-relative path
+    pub file: Option<String>,
+    /// First and last line (1-based, inclusive) in the checked-out version
+    pub lines: Option<(u32, u32)>,
+    pub title: String,
+    pub message: String,
+}
+
+/// Render annotations in a CI format.
+///
+/// `suites` lists every group that was checked, so groups without findings
+/// still show up as passing in JUnit reports. Text and JSON are rendered by
+/// the commands themselves and yield an empty string here.
+pub fn render(format: OutputFormat, suites: &[String], annotations: &[Annotation]) -> String {
+    match format {
+        OutputFormat::Sarif => sarif(annotations),
+        OutputFormat::Junit => junit(suites, annotations),
+        OutputFormat::Gha => gha(annotations),
+        OutputFormat::Text | OutputFormat::Json => String::new(),
+    }
+}
+
+/// Line ranges in this side's file where the pair's changes collide.
+///
+/// Falls back to the other side's changes (mapped into this side's lines)
+/// when nothing collides, e.g. when the other worktree only has active
+/// changes. Ranges are 1-based, inclusive and deduplicated.
+pub fn conflict_lines(diff: &PairFileDiff) -> Vec<(u32, u32)> {
+    let mut ranges: Vec<(u32, u32)> = diff
+        .regions(0)
+        .iter()
+        .map(|r| {
+            (
+                r.ours.new_start,
+                r.ours.new_start + r.ours.new_lines.max(1) - 1,
+            )
+        })
+        .collect();
+    if ranges.is_empty() {
+        ranges = diff
+            .theirs_hunks
+            .iter()
+            .map(|h| {
+                let start = diff.map_base_line(h.old_start);
+                (start, start + h.old_lines.max(1) - 1)
+            })
+            .collect();
+    }
+    ranges.sort_unstable();
+    ranges.dedup();
+    ranges
+}
+
+/// SARIF 2.1.0 log with one result per annotation
+fn sarif(annotations: &[Annotation]) -> String {
+    let rules: Vec<_> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id(),
+                "shortDescription": { "text": rule.description() },
+            })
+        })
+        .collect();
+    let results: Vec<_> = annotations
+        .iter()
+        .map(|a| {
+            let mut result = json!({
+                "ruleId": a.rule.id(),
+                "level": match a.level {
+                    Level::Error => "error",
+                    Level::Warning => "warning",
+                    Level::Notice => "note",
+                },
+                "message": { "text": format!("{}: {}", a.title, a.message) },
+            });
+            if let Some(file) = &a.file {
+                let mut location = json!({ "artifactLocation": { "uri": file } });
+                if let Some((start, end)) = a.lines {
+                    location["region"] = json!({ "startLine": start, "endLine": end });
+                }
+                result["locations"] = json!([{ "physicalLocation": location }]);
+            }
+            result
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "clash",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/clash-sh/clash",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).expect("SARIF log is always serializable")
+}
+
+/// JUnit XML with a test suite per group and a test case per file.
+///
+/// Errors and warnings fail their test case; notices are attached as output.
+fn junit(suites: &[String], annotations: &[Annotation]) -> String {
+    let describe = |a: &Annotation| match a.lines {
+        Some((start, end)) => format!("{} (lines {}-{})", a.message, start, end),
+        None => a.message.clone(),
+    };
+
+    let mut body = String::new();
+    let (mut total_tests, mut total_failures) = (0, 0);
+    for suite in suites {
+        // Test cases in order of first finding: (file, annotations)
+        let mut cases: Vec<(&str, Vec<&Annotation>)> = Vec::new();
+        for a in annotations.iter().filter(|a| &a.suite == suite) {
+            let file = a.file.as_deref().unwrap_or(suite);
+            match cases.iter_mut().find(|(f, _)| *f == file) {
+                Some((_, found)) => found.push(a),
+                None => cases.push((file, vec![a])),
+            }
+        }
+        if cases.is_empty() {
+            cases.push(("no conflicts", Vec::new()));
+        }
+        let failures = cases
+            .iter()
+            .filter(|(_, found)| found.iter().any(|a| a.level != Level::Notice))
+            .count();
+        total_tests += cases.len();
+        total_failures += failures;
+
+        let _ = writeln!(
+            body,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+            xml_escape(suite),
+            cases.len(),
+            failures
+        );
+        for (file, found) in cases {
+            if found.is_empty() {
+                let _ = writeln!(body, "    <testcase name=\"{}\"/>", xml_escape(file));
+                continue;
+            }
+            let _ = writeln!(
+                body,
+                "    <testcase name=\"{}\" classname=\"{}\">",
+                xml_escape(file),
+                xml_escape(suite)
+            );
+            let (failed, notices): (Vec<&Annotation>, Vec<&Annotation>) =
+                found.into_iter().partition(|a| a.level != Level::Notice);
+            if let Some(first) = failed.first() {
+                let text: Vec<String> = failed.iter().map(|a| describe(a)).collect();
+                let _ = writeln!(
+                    body,
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>",
+                    first.rule.id(),
+                    xml_escape(&first.title),
+                    xml_escape(&text.join("\n"))
+                );
+            }
+            if !notices.is_empty() {
+                let text: Vec<String> = notices.iter().map(|a| describe(a)).collect();
+                let _ = writeln!(
+                    body,
+                    "      <system-out>{}</system-out>",
+                    xml_escape(&text.join("\n"))
+                );
+            }
+            body.push_str("    </testcase>\n");
+        }
+        body.push_str("  </testsuite>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"clash\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>",
+        total_tests, total_failures, body
+    )
+}
+
+/// GitHub Actions workflow commands, one per annotation
+fn gha(annotations: &[Annotation]) -> String {
+    annotations
+        .iter()
+        .map(|a| {
+            let command = match a.level {
+                Level::Error => "error",
+                Level::Warning => "warning",
+                Level::Notice => "notice",
+            };
+            let mut properties = Vec::new();
+            if let Some(file) = &a.file {
+                properties.push(format!("file={}", gha_property(file)));
+            }
+            if let Some((start, end)) = a.lines {
+                properties.push(format!("line={}", start));
+                properties.push(format!("endLine={}", end));
+            }
+            properties.push(format!("title={}", gha_property(&a.title)));
+            format!(
+                "::{} {}::{}",
+                command,
+                properties.join(","),
+                gha_data(&a.message)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape a workflow command's message
+fn gha_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value
+fn gha_property(text: &str) -> String {
+    gha_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Escape text for XML and HTML content and attributes
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(level: Level) -> Annotation {
+        Annotation {
+            suite: "a vs b".to_string(),
+            rule: Rule::MergeConflict,
+            level,
+            file: Some("src/a,b.rs".to_string()),
+            lines: Some((3, 5)),
+            title: "Conflict: a & b".to_string(),
+            message: "line one\nline <two>".to_string(),
+        }
+    }
+
+    #[test]
+    fn gha_escapes_properties_and_data() {
+        assert_eq!(
+            gha(&[annotation(Level::Error)]),
+            "::error file=src/a%2Cb.rs,line=3,endLine=5,title=Conflict%3A a & b::line one%0Aline <two>"
+        );
+    }
+
+    #[test]
+    fn junit_fails_on_errors_and_lists_clean_suites() {
+        let suites = ["a vs b".to_string(), "a vs c".to_string()];
+        let xml = junit(&suites, &[annotation(Level::Error)]);
+        assert!(xml.contains("<testsuites name=\"clash\" tests=\"2\" failures=\"1\">"));
+        assert!(xml.contains("message=\"Conflict: a &amp; b\""));
+        assert!(xml.contains("line &lt;two&gt; (lines 3-5)</failure>"));
+        assert!(xml.contains("<testcase name=\"no conflicts\"/>"));
+
+        let xml = junit(&suites[..1], &[annotation(Level::Notice)]);
+        assert!(xml.contains("failures=\"0\""));
+        assert!(xml.contains("<system-out>"));
+    }
+}
@@ -0,0 +1,426 @@
+//! Git hooks that give humans the same protection as agents
+//!
+//! `clash hooks install` writes small scripts into the repository's hooks
+//! directory (shared by every worktree) that call back into clash:
+//!
+//! - **pre-commit** runs `clash precommit`, which merges each staged file
+//!   against every other worktree.
+//! - **pre-push** runs `clash prepush`, which merges each pushed commit with
+//!   the remote's default branch and every other worktree.
+//! - **post-merge** runs `clash brief` to show where the others are working.
+//!
+//! With `[git_hooks] mode = "block"` in `.clash.toml` (or `--block`),
+//! conflicts abort the commit or push; by default they are only reported.
+//!
+//! `--force` moves hooks clash didn't write (husky, lefthook, ...) aside to
+//! `<hook>.pre-clash`; clash's scripts run them first and stop if they fail.
+
+use clash_sh::config::{Config, GitHookMode};
+use clash_sh::{Suppressions, Worktree, WorktreeManager};
+use colored::Colorize;
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+
+/// Marks hook scripts written by clash, so reinstalling can replace them
+const HOOK_MARKER: &str = "# Installed by clash (clash hooks install)";
+
+/// Hook name, the clash command it runs, and whether git feeds it stdin
+const HOOKS: &[(&str, &str, bool)] = &[
+    ("pre-commit", "clash precommit", false),
+    ("pre-push", "clash prepush \"$@\"", true),
+    ("post-merge", "clash brief", false),
+];
+
+/// Suffix of a replaced hook, which clash's script chains to
+const BACKUP_SUFFIX: &str = ".pre-clash";
+
+/// All-zero object id git uses for a ref being created or deleted
+const NULL_SHA: &str = "0000000000000000000000000000000000000000";
+
+/// Install clash's git hooks into the repository's hooks directory.
+///
+/// Respects `core.hooksPath`. Hooks that clash didn't write are left alone
+/// unless `force` is set, in which case they're renamed to
+/// `<hook>.pre-clash` and run before clash.
+pub fn run_hooks_install(worktrees: &WorktreeManager, force: bool) -> Result<(), String> {
+    let dir = hooks_dir(worktrees)?;
+    let foreign: Vec<&str> = HOOKS
+        .iter()
+        .map(|(name, _, _)| *name)
+        .filter(|name| {
+            std::fs::read_to_string(dir.join(name))
+                .is_ok_and(|script| !script.contains(HOOK_MARKER))
+        })
+        .collect();
+    if !foreign.is_empty() && !force {
+        return Err(format!(
+            "{} already exist{} in {}; use --force to replace",
+            foreign.join(", "),
+            if foreign.len() == 1 { "s" } else { "" },
+            dir.display()
+        ));
+    }
+    let backups: Vec<PathBuf> = foreign
+        .iter()
+        .map(|name| dir.join(format!("{}{}", name, BACKUP_SUFFIX)))
+        .collect();
+    if let Some(taken) = backups.iter().find(|backup| backup.exists()) {
+        return Err(format!(
+            "{} already exists; move it away before replacing the hook",
+            taken.display()
+        ));
+    }
+    for (name, backup) in foreign.iter().zip(&backups) {
+        std::fs::rename(dir.join(name), backup)
+            .map_err(|e| format!("Failed to move {} aside: {}", name, e))?;
+        println!(
+            "Moved {} to {}",
+            name,
+            backup.display().to_string().bright_yellow()
+        );
+    }
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    for (name, command, stdin) in HOOKS {
+        let path = dir.join(name);
+        std::fs::write(&path, hook_script(command, *stdin))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+        }
+        println!("Installed {}", path.display().to_string().bright_green());
+    }
+    Ok(())
+}
+
+/// Script for a hook: run the hook it replaced (if any), then clash.
+///
+/// Hooks that read stdin (pre-push's ref lines) get a copy of it each.
+fn hook_script(command: &str, stdin: bool) -> String {
+    let backup = format!("\"$0{}\"", BACKUP_SUFFIX);
+    let (capture, feed) = if stdin {
+        ("input=$(cat)\n", "printf '%s\\n' \"$input\" | ")
+    } else {
+        ("", "")
+    };
+    // Without clash on PATH the hook steps aside instead of breaking git
+    format!(
+        "#!/bin/sh\n{marker}\n{capture}\
+         if [ -x {backup} ]; then {feed}{backup} \"$@\" || exit $?; fi\n\
+         command -v clash >/dev/null 2>&1 || exit 0\n\
+         {feed}{exec}{command}\n",
+        marker = HOOK_MARKER,
+        exec = if stdin { "" } else { "exec " },
+    )
+}
+
+/// Check the staged files of the current worktree against every other
+/// worktree. Returns exit code 1 if the commit should be blocked.
+pub fn run_precommit(
+    worktrees: &WorktreeManager,
+    mode: Option<GitHookMode>,
+) -> Result<i32, String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+    let mode = resolve_mode(worktrees, mode)?;
+    let (conflicts, warnings) = staged_findings(worktrees, current)?;
+    Ok(report("commit", mode, &conflicts, &warnings))
+}
+
+/// Conflicts and warnings for the files staged in `current`
+fn staged_findings(
+    worktrees: &WorktreeManager,
+    current: &Worktree,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let staged = current.staged_files().map_err(|e| e.to_string())?;
+    let suppressions = Suppressions::load(worktrees);
+
+    let mut conflicts = Vec::new();
+    let mut warnings = Vec::new();
+    for other in worktrees.iter().filter(|w| w.id != current.id) {
+        let active = other.uncommitted_files().unwrap_or_default();
+        for path in &staged {
+            if suppressions.is_suppressed(current, other, path) {
+                continue;
+            }
+            // A staged deletion can't be simulated, so fall back to HEAD
+            let content = current.staged_file_contents(path);
+            match current.file_conflicts_with(other, path, content.as_deref()) {
+                Ok(true) => conflicts.push(format!("{}: conflicts with {}", path, label(other))),
+                Ok(false) => {}
+                Err(e) => warnings.push(format!("{}: cannot check {}: {}", path, label(other), e)),
+            }
+            if active.contains(path) {
+                warnings.push(format!("{}: uncommitted changes in {}", path, label(other)));
+            }
+        }
+    }
+    Ok((conflicts, warnings))
+}
+
+/// Check the commits being pushed against the remote's default branch and
+/// every other worktree. Reads git's pre-push ref lines from stdin and
+/// returns exit code 1 if the push should be blocked.
+pub fn run_prepush(
+    worktrees: &WorktreeManager,
+    remote: Option<&str>,
+    mode: Option<GitHookMode>,
+) -> Result<i32, String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+    let mode = resolve_mode(worktrees, mode)?;
+
+    let mut input = String::new();
+    if !std::io::stdin().is_terminal() {
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| format!("Failed to read pushed refs: {}", e))?;
+    }
+    // `git push` with no refs on stdin (or a manual run) checks HEAD
+    let mut pushed: Vec<(String, String)> = input
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let local_ref = fields.next()?;
+            let local_sha = fields.next()?;
+            (local_sha != NULL_SHA).then(|| (local_ref.to_string(), local_sha.to_string()))
+        })
+        .collect();
+    if input.trim().is_empty() {
+        pushed.push((format!("refs/heads/{}", current.branch), "HEAD".to_string()));
+    }
+
+    let target = remote.and_then(|remote| default_branch(current, remote));
+    let (conflicts, warnings) = push_findings(worktrees, current, &pushed, target.as_deref());
+    Ok(report("push", mode, &conflicts, &warnings))
+}
+
+/// Conflicts and warnings for pushing `pushed` (ref, commit) from `current`,
+/// against `target` (e.g. `origin/main`) and every other worktree's HEAD
+fn push_findings(
+    worktrees: &WorktreeManager,
+    current: &Worktree,
+    pushed: &[(String, String)],
+    target: Option<&str>,
+) -> (Vec<String>, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let mut warnings = Vec::new();
+    for (local_ref, sha) in pushed {
+        let branch = local_ref.strip_prefix("refs/heads/").unwrap_or(local_ref);
+
+        let mut against: Vec<(String, String)> = Vec::new();
+        if let Some(target) = target {
+            against.push((target.to_string(), target.to_string()));
+        }
+        for other in worktrees.iter().filter(|w| w.id != current.id) {
+            if let Some(head) = other.head_commit() {
+                against.push((label(other), head.id));
+            }
+        }
+
+        for (name, rev) in against {
+            match current.revs_conflict(sha, &rev) {
+                Ok(files) => conflicts.extend(
+                    files
+                        .into_iter()
+                        .map(|f| format!("{}: {} conflicts with {}", f, branch, name)),
+                ),
+                Err(e) => warnings.push(format!("{}: cannot check {}: {}", branch, name, e)),
+            }
+        }
+    }
+    (conflicts, warnings)
+}
+
+/// Print findings to stderr and return the hook's exit code
+fn report(action: &str, mode: GitHookMode, conflicts: &[String], warnings: &[String]) -> i32 {
+    for warning in warnings {
+        eprintln!("{} {}", "clash:".bright_yellow(), warning);
+    }
+    if conflicts.is_empty() {
+        return 0;
+    }
+    for conflict in conflicts {
+        eprintln!("{} {}", "clash:".bright_red().bold(), conflict);
+    }
+    match mode {
+        GitHookMode::Block => {
+            eprintln!(
+                "{} {} blocked; use --no-verify to skip",
+                "clash:".bright_red().bold(),
+                action
+            );
+            1
+        }
+        GitHookMode::Warn => 0,
+    }
+}
+
+/// The mode given on the command line, or the one from `.clash.toml`
+fn resolve_mode(
+    worktrees: &WorktreeManager,
+    mode: Option<GitHookMode>,
+) -> Result<GitHookMode, String> {
+    match mode {
+        Some(mode) => Ok(mode),
+        None => Ok(Config::load(worktrees)
+            .map_err(|e| e.to_string())?
+            .git_hooks
+            .mode),
+    }
+}
+
+/// The repository's hooks directory: `core.hooksPath` if set (relative to
+/// the main worktree), otherwise `hooks/` in the common git directory
+pub fn hooks_dir(worktrees: &WorktreeManager) -> Result<PathBuf, String> {
+    let root = worktrees
+        .main()
+        .map(|main| main.path.clone())
+        .ok_or("no main worktree found")?;
+    let repo = gix::open(&root).map_err(|e| e.to_string())?;
+    let configured = repo
+        .config_snapshot()
+        .string("core.hooksPath")
+        .map(|path| PathBuf::from(path.to_string()));
+    Ok(match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => root.join(path),
+        None => worktrees.common_dir().join("hooks"),
+    })
+}
+
+/// The branch `refs/remotes/<remote>/HEAD` points at, as `<remote>/<branch>`
+fn default_branch(worktree: &Worktree, remote: &str) -> Option<String> {
+    let repo = gix::open(&worktree.path).ok()?;
+    let head = repo
+        .find_reference(format!("refs/remotes/{}/HEAD", remote).as_str())
+        .ok()?;
+    let target = head.target().try_name()?.as_bstr().to_string();
+    target.strip_prefix("refs/remotes/").map(str::to_string)
+}
+
+/// Worktree id and branch, e.g. `feature-a [feat-a]`
+fn label(worktree: &Worktree) -> String {
+    format!("{} [{}]", worktree.id, worktree.branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    #[test]
+    fn staged_conflicts_block_only_in_block_mode() {
+        let repo = TestRepo::new(&[("a.txt", "base\n")]);
+        repo.add_worktree("feature");
+        repo.write("feature", "a.txt", "theirs\n");
+        repo.commit("feature", "theirs");
+        repo.write("main", "a.txt", "ours\n");
+        repo.git("main", &["add", "a.txt"]);
+
+        let worktrees = repo.manager();
+        let current = worktrees.find("main").unwrap();
+        let (conflicts, warnings) = staged_findings(&worktrees, current).unwrap();
+        assert_eq!(conflicts.len(), 1, "{:?}", conflicts);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            report("commit", GitHookMode::Block, &conflicts, &warnings),
+            1
+        );
+        assert_eq!(
+            report("commit", GitHookMode::Warn, &conflicts, &warnings),
+            0
+        );
+        assert_eq!(report("commit", GitHookMode::Block, &[], &warnings), 0);
+    }
+
+    #[test]
+    fn push_is_not_checked_against_the_pushing_worktree() {
+        let repo = TestRepo::new(&[("a.txt", "base\n")]);
+        repo.add_worktree("feature");
+        repo.git("main", &["checkout", "-q", "-b", "topic"]);
+        repo.write("main", "a.txt", "topic\n");
+        repo.commit("main", "topic");
+        let topic = repo.git("main", &["rev-parse", "topic"]).trim().to_string();
+        // Push `topic` from a detached HEAD that edits the same line
+        repo.git("main", &["checkout", "-q", "--detach", "main"]);
+        repo.write("main", "a.txt", "detached\n");
+        repo.commit("main", "detached");
+
+        let worktrees = repo.manager();
+        let current = worktrees.find("main").unwrap();
+        let pushed = [("refs/heads/topic".to_string(), topic)];
+        let (conflicts, warnings) = push_findings(&worktrees, current, &pushed, None);
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        repo.write("feature", "a.txt", "feature\n");
+        repo.commit("feature", "feature");
+        let worktrees = repo.manager();
+        let current = worktrees.find("main").unwrap();
+        let (conflicts, _) = push_findings(&worktrees, current, &pushed, None);
+        assert_eq!(conflicts.len(), 1, "{:?}", conflicts);
+        assert_eq!(report("push", GitHookMode::Block, &conflicts, &[]), 1);
+        assert_eq!(report("push", GitHookMode::Warn, &conflicts, &[]), 0);
+    }
+
+    #[test]
+    fn force_install_keeps_and_chains_existing_hooks() {
+        let repo = TestRepo::new(&[("a.txt", "base\n")]);
+        let worktrees = repo.manager();
+        let dir = hooks_dir(&worktrees).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        let ran = repo.root().join("ran");
+        std::fs::write(
+            dir.join("pre-push"),
+            format!("#!/bin/sh\ncat > {}\nexit 3\n", ran.display()),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir.join("pre-push"), std::fs::Permissions::from_mode(0o755))
+                .unwrap();
+        }
+
+        assert!(run_hooks_install(&worktrees, false).is_err());
+        run_hooks_install(&worktrees, true).unwrap();
+        assert!(dir.join("pre-push.pre-clash").exists());
+        assert!(
+            std::fs::read_to_string(dir.join("pre-push"))
+                .unwrap()
+                .contains(HOOK_MARKER)
+        );
+
+        // The old hook sees git's ref lines and its failure stops the push
+        let mut child = std::process::Command::new("sh")
+            .arg(dir.join("pre-push"))
+            .args(["origin", "url"])
+            .env("PATH", "/usr/bin:/bin")
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        use std::io::Write;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(b"refs/heads/main abc refs/heads/main def\n")
+            .unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(3));
+        assert_eq!(
+            std::fs::read_to_string(&ran).unwrap(),
+            "refs/heads/main abc refs/heads/main def\n"
+        );
+
+        // Reinstalling over clash's own hooks keeps the backup in place
+        run_hooks_install(&worktrees, false).unwrap();
+        assert!(dir.join("pre-push.pre-clash").exists());
+    }
+}
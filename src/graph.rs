@@ -0,0 +1,346 @@
+//! `clash graph`: the conflict graph as Graphviz DOT or Mermaid
+//!
+//! Worktrees are nodes and conflicting pairs are edges, weighted by the
+//! number of conflicting files and colored by how the files diverged. With
+//! `--cluster`, worktrees whose work is entangled (connected through
+//! conflicts) are grouped, which shows which agents have to coordinate.
+
+use crate::status::check_pairs;
+use clash_sh::{DivergenceKind, Side, WorktreeManager, WorktreePairConflict};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Format of `clash graph`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT (render with `dot -Tsvg`)
+    Dot,
+    /// Mermaid flowchart (renders in GitHub Markdown)
+    Mermaid,
+}
+
+/// What an edge stands for, which decides its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    /// All conflicting files diverged the same way
+    Kind(DivergenceKind),
+    /// Files diverged in different ways
+    Mixed,
+    /// The pair couldn't be checked
+    Failed,
+}
+
+impl EdgeKind {
+    fn color(self) -> &'static str {
+        match self {
+            EdgeKind::Kind(DivergenceKind::Content) => "#d73a49",
+            EdgeKind::Kind(DivergenceKind::AddAdd) => "#e36209",
+            EdgeKind::Kind(DivergenceKind::ModifyDelete) => "#6f42c1",
+            EdgeKind::Kind(DivergenceKind::None) => "#959da5",
+            EdgeKind::Mixed => "#b08800",
+            EdgeKind::Failed => "#959da5",
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            EdgeKind::Kind(kind) => kind.to_string(),
+            EdgeKind::Mixed => "mixed".to_string(),
+            EdgeKind::Failed => "check failed".to_string(),
+        }
+    }
+}
+
+/// A conflicting (or unchecked) pair, by worktree index
+struct Edge {
+    from: usize,
+    to: usize,
+    files: usize,
+    kind: EdgeKind,
+}
+
+/// Print the conflict graph, or write it to `output`.
+///
+/// Ignored and acknowledged conflicts are left out unless `all` is set.
+pub fn run_graph(
+    worktrees: &WorktreeManager,
+    format: GraphFormat,
+    cluster: bool,
+    all: bool,
+    output: Option<&Path>,
+) -> Result<(), String> {
+    let (pairs, _) = check_pairs(worktrees, all);
+    let edges = build_edges(worktrees, &pairs);
+    let groups = if cluster {
+        let links: Vec<(usize, usize)> = edges
+            .iter()
+            .filter(|e| e.kind != EdgeKind::Failed)
+            .map(|e| (e.from, e.to))
+            .collect();
+        components(worktrees.len(), &links)
+            .into_iter()
+            .filter(|group| group.len() > 1)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let text = match format {
+        GraphFormat::Dot => dot(worktrees, &edges, &groups),
+        GraphFormat::Mermaid => mermaid(worktrees, &edges, &groups),
+    };
+    match output {
+        Some(path) => std::fs::write(path, text)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+/// Edges for pairs with conflicting files or a failed check
+fn build_edges(worktrees: &WorktreeManager, pairs: &[WorktreePairConflict]) -> Vec<Edge> {
+    let index: HashMap<&str, usize> = worktrees
+        .iter()
+        .enumerate()
+        .map(|(i, wt)| (wt.id.as_str(), i))
+        .collect();
+
+    pairs
+        .iter()
+        .filter(|pair| pair.error.is_some() || !pair.conflicting_files.is_empty())
+        .filter_map(|pair| {
+            let from = *index.get(pair.wt1.id.as_str())?;
+            let to = *index.get(pair.wt2.id.as_str())?;
+            let kind = if pair.error.is_some() {
+                EdgeKind::Failed
+            } else {
+                // Merge conflicts are computed on HEAD, so classify the commits too
+                let ours = &worktrees.all()[from];
+                let theirs = &worktrees.all()[to];
+                let mut kinds = pair.conflicting_files.iter().map(|file| {
+                    ours.diff_file_against(theirs, file, Side::Head)
+                        .map_or(DivergenceKind::None, |diff| diff.kind())
+                });
+                let first = kinds.next().unwrap_or(DivergenceKind::None);
+                if kinds.all(|kind| kind == first) {
+                    EdgeKind::Kind(first)
+                } else {
+                    EdgeKind::Mixed
+                }
+            };
+            Some(Edge {
+                from,
+                to,
+                files: pair.conflicting_files.len(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Connected components of an undirected graph with `nodes` nodes, each
+/// sorted, in order of their smallest node
+fn components(nodes: usize, links: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    fn root(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut parent: Vec<usize> = (0..nodes).collect();
+    for &(a, b) in links {
+        let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+        parent[ra.max(rb)] = ra.min(rb);
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: HashMap<usize, usize> = HashMap::new();
+    for node in 0..nodes {
+        let r = root(&mut parent, node);
+        let group = *group_of.entry(r).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(node);
+    }
+    groups
+}
+
+fn edge_label(edge: &Edge) -> String {
+    match edge.kind {
+        EdgeKind::Failed => edge.kind.label(),
+        kind => format!(
+            "{} file{} ({})",
+            edge.files,
+            if edge.files == 1 { "" } else { "s" },
+            kind.label()
+        ),
+    }
+}
+
+fn dot(worktrees: &WorktreeManager, edges: &[Edge], groups: &[Vec<usize>]) -> String {
+    let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let node = |i: usize| {
+        let wt = &worktrees.all()[i];
+        format!(
+            "n{} [label=\"{}\\n[{}]\"];",
+            i,
+            quote(&wt.id),
+            quote(&wt.branch)
+        )
+    };
+
+    let mut out = String::from("graph clash {\n  node [shape=box, style=rounded];\n");
+    let grouped: Vec<usize> = groups.iter().flatten().copied().collect();
+    for (g, group) in groups.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  subgraph cluster_{} {{\n    label=\"Group {}\";\n    style=dashed;",
+            g,
+            g + 1
+        );
+        for &i in group {
+            let _ = writeln!(out, "    {}", node(i));
+        }
+        out.push_str("  }\n");
+    }
+    for i in (0..worktrees.len()).filter(|i| !grouped.contains(i)) {
+        let _ = writeln!(out, "  {}", node(i));
+    }
+    for edge in edges {
+        let style = if edge.kind == EdgeKind::Failed {
+            ", style=dashed".to_string()
+        } else {
+            format!(
+                ", weight={}, penwidth={}",
+                edge.files,
+                pen_width(edge.files)
+            )
+        };
+        let _ = writeln!(
+            out,
+            "  n{} -- n{} [label=\"{}\", color=\"{}\", fontcolor=\"{}\"{}];",
+            edge.from,
+            edge.to,
+            edge_label(edge),
+            edge.kind.color(),
+            edge.kind.color(),
+            style
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mermaid(worktrees: &WorktreeManager, edges: &[Edge], groups: &[Vec<usize>]) -> String {
+    // Labels are rendered as HTML and `#...;` is Mermaid's entity syntax, so
+    // `#` goes first and the rest become entities
+    let escape = |text: &str| {
+        text.replace('#', "#35;")
+            .replace('&', "#amp;")
+            .replace('<', "#lt;")
+            .replace('>', "#gt;")
+            .replace('"', "#quot;")
+    };
+    let node = |i: usize| {
+        let wt = &worktrees.all()[i];
+        format!(
+            "n{}[\"{}<br/>[{}]\"]",
+            i,
+            escape(&wt.id),
+            escape(&wt.branch)
+        )
+    };
+
+    let mut out = String::from("flowchart LR\n");
+    let grouped: Vec<usize> = groups.iter().flatten().copied().collect();
+    for (g, group) in groups.iter().enumerate() {
+        let _ = writeln!(out, "  subgraph group{}[\"Group {}\"]", g + 1, g + 1);
+        for &i in group {
+            let _ = writeln!(out, "    {}", node(i));
+        }
+        out.push_str("  end\n");
+    }
+    for i in (0..worktrees.len()).filter(|i| !grouped.contains(i)) {
+        let _ = writeln!(out, "  {}", node(i));
+    }
+    for edge in edges {
+        let link = if edge.kind == EdgeKind::Failed {
+            "-.-"
+        } else {
+            "---"
+        };
+        let _ = writeln!(
+            out,
+            "  n{} {}|\"{}\"| n{}",
+            edge.from,
+            link,
+            edge_label(edge),
+            edge.to
+        );
+    }
+    for (i, edge) in edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  linkStyle {} stroke:{},stroke-width:{}px",
+            i,
+            edge.kind.color(),
+            pen_width(edge.files)
+        );
+    }
+    out
+}
+
+/// Line width for an edge: thicker with more conflicting files, capped
+fn pen_width(files: usize) -> usize {
+    1 + files.min(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    #[test]
+    fn names_are_escaped_in_both_formats() {
+        let repo = TestRepo::new(&[("a.txt", "a\n")]);
+        let name = "x\"<b>&#1;";
+        repo.add_worktree(name);
+        let worktrees = repo.manager();
+        let edges = [Edge {
+            from: 0,
+            to: 1,
+            files: 2,
+            kind: EdgeKind::Kind(DivergenceKind::Content),
+        }];
+        let groups = [vec![0, 1]];
+
+        let dot = dot(&worktrees, &edges, &groups);
+        assert!(
+            dot.contains("[label=\"x\\\"<b>&#1;\\n[x\\\"<b>&#1;]\"]"),
+            "{}",
+            dot
+        );
+        assert!(dot.contains("n0 -- n1 [label=\"2 files"), "{}", dot);
+
+        let mermaid = mermaid(&worktrees, &edges, &groups);
+        let label = "x#quot;#lt;b#gt;#amp;#35;1;";
+        assert!(
+            mermaid.contains(&format!("[\"{}<br/>[{}]\"]", label, label)),
+            "{}",
+            mermaid
+        );
+        assert!(!mermaid.contains("<b>"), "{}", mermaid);
+        assert!(mermaid.contains("n0 ---|\"2 files"), "{}", mermaid);
+    }
+
+    #[test]
+    fn components_group_connected_nodes() {
+        let groups = components(6, &[(0, 3), (4, 1), (3, 5)]);
+        assert_eq!(groups, vec![vec![0, 3, 5], vec![1, 4], vec![2]]);
+    }
+}
@@ -0,0 +1,417 @@
+//! `clash init`: wire clash into a repository
+//!
+//! Detects which agents the repository is set up for and merges clash's
+//! hooks into their settings files, or adds an instruction snippet for
+//! agents without hooks. Also writes a starter `.clash.toml` and, on
+//! request, the git hooks. Running it again changes nothing: hooks already
+//! present are kept and the snippet is replaced between its markers.
+
+use clash_sh::WorktreeManager;
+use clash_sh::config::CONFIG_FILE;
+use clash_sh::diff_lines;
+use colored::Colorize;
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
+
+/// Agents `clash init` knows how to set up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InitAgent {
+    /// Claude Code: hooks in `.claude/settings.json`
+    Claude,
+    /// Cursor: hooks in `.cursor/hooks.json`
+    Cursor,
+    /// Gemini CLI: hooks in `.gemini/settings.json`
+    Gemini,
+    /// Windsurf: hooks in `.windsurf/hooks.json`
+    Windsurf,
+    /// Codex CLI: instructions in `AGENTS.md`
+    Codex,
+    /// GitHub Copilot: instructions in `.github/copilot-instructions.md`
+    Copilot,
+}
+
+/// How an agent's hook entries are shaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookStyle {
+    /// `{"matcher": ..., "hooks": [{"type": "command", "command": ...}]}`
+    Nested,
+    /// `{"command": ...}`
+    Flat,
+}
+
+/// A hook to register: event, optional tool matcher and command
+type HookSpec = (&'static str, Option<&'static str>, &'static str);
+
+/// Marks the start of the instruction snippet
+const SNIPPET_START: &str = "<!-- clash:start -->";
+
+/// Marks the end of the instruction snippet
+const SNIPPET_END: &str = "<!-- clash:end -->";
+
+/// Instructions for agents that can't run hooks
+const SNIPPET: &str = "\
+## Parallel worktrees (clash)
+
+IMPORTANT: Before editing any file, run `clash check <file>` to check for merge conflicts
+with other worktrees. If conflicts are detected, examine the conflicting files and adapt
+your approach to avoid or minimize them.
+
+Run `clash brief` when starting a task to see where the other worktrees are working, and
+`clash status` periodically (especially before and after commits) for a full conflict
+overview. Run `clash --help` for all available commands.";
+
+/// Starter `.clash.toml` with the defaults spelled out
+const STARTER_CONFIG: &str = r#"# Clash configuration, shared by every worktree
+# See https://github.com/clash-sh/clash#hook-policy
+
+[policy]
+# Decision when the check itself fails: "allow" (fail open), "ask" or "deny"
+on_error = "allow"
+
+# Rules are tried in order; the first one matching a finding decides it.
+# Findings: merge_conflict, introduces_conflict, active_changes,
+# distant_conflict, claimed, planned. Decisions: ignore, allow, ask, deny.
+#
+# [[policy.rules]]
+# when = ["merge_conflict", "introduces_conflict"]
+# paths = ["src/auth/**"]
+# decision = "deny"
+
+[git_hooks]
+# "warn" reports conflicts on commit and push, "block" aborts them
+mode = "warn"
+"#;
+
+/// A file `clash init` would create or change
+struct FileChange {
+    path: PathBuf,
+    old: Option<String>,
+    new: String,
+}
+
+/// Set up clash for the agents in use (detected unless given), write a
+/// starter `.clash.toml` and optionally install the git hooks.
+///
+/// With `dry_run`, prints the changes as diffs without writing anything.
+pub fn run_init(
+    worktrees: &WorktreeManager,
+    agents: &[InitAgent],
+    git_hooks: bool,
+    dry_run: bool,
+) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+    let root = &current.path;
+
+    let agents = if agents.is_empty() {
+        detect_agents(root)
+    } else {
+        agents.to_vec()
+    };
+    if agents.is_empty() {
+        println!(
+            "No agents detected; use {} to choose (e.g. --agent claude)",
+            "--agent".bright_blue()
+        );
+    }
+
+    let mut changes = Vec::new();
+    for agent in &agents {
+        changes.push(agent_change(*agent, root)?);
+    }
+    let config_root = worktrees.main().map_or(root, |main| &main.path);
+    let config_path = config_root.join(CONFIG_FILE);
+    if !config_path.exists() {
+        changes.push(FileChange {
+            path: config_path,
+            old: None,
+            new: STARTER_CONFIG.to_string(),
+        });
+    }
+    changes.retain(|c| c.old.as_deref() != Some(c.new.as_str()));
+
+    for change in &changes {
+        let shown = change.path.strip_prefix(root).unwrap_or(&change.path);
+        if dry_run {
+            print_diff(
+                shown,
+                change.old.as_deref().unwrap_or_default(),
+                &change.new,
+            );
+            continue;
+        }
+        if let Some(parent) = change.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&change.path, &change.new)
+            .map_err(|e| format!("Failed to write {}: {}", change.path.display(), e))?;
+        let verb = if change.old.is_some() {
+            "Updated"
+        } else {
+            "Created"
+        };
+        println!("{} {}", verb, shown.display().to_string().bright_green());
+    }
+    if changes.is_empty() {
+        println!("{} Already set up", "✓".bright_green().bold());
+    }
+
+    if git_hooks {
+        if dry_run {
+            println!(
+                "Would install git hooks into {}",
+                crate::githooks::hooks_dir(worktrees)?.display()
+            );
+        } else {
+            crate::githooks::run_hooks_install(worktrees, false)?;
+        }
+    }
+    Ok(())
+}
+
+/// Agents whose config files or directories exist in the worktree
+fn detect_agents(root: &Path) -> Vec<InitAgent> {
+    let markers: &[(InitAgent, &[&str])] = &[
+        (InitAgent::Claude, &[".claude", "CLAUDE.md"]),
+        (InitAgent::Cursor, &[".cursor", ".cursorrules"]),
+        (InitAgent::Gemini, &[".gemini", "GEMINI.md"]),
+        (InitAgent::Windsurf, &[".windsurf", ".windsurfrules"]),
+        (InitAgent::Codex, &[".codex", "AGENTS.md"]),
+        (InitAgent::Copilot, &[".github/copilot-instructions.md"]),
+    ];
+    markers
+        .iter()
+        .filter(|(_, paths)| paths.iter().any(|p| root.join(p).exists()))
+        .map(|(agent, _)| *agent)
+        .collect()
+}
+
+/// The file an agent is set up through, with clash merged in
+fn agent_change(agent: InitAgent, root: &Path) -> Result<FileChange, String> {
+    const EDIT_TOOLS: &str = "Write|Edit|MultiEdit|Bash";
+    const GEMINI_TOOLS: &str = "write_file|replace|run_shell_command";
+
+    let (file, style, hooks): (&str, HookStyle, &[HookSpec]) = match agent {
+        InitAgent::Claude => (
+            ".claude/settings.json",
+            HookStyle::Nested,
+            &[
+                ("PreToolUse", Some(EDIT_TOOLS), "clash check"),
+                ("PostToolUse", Some(EDIT_TOOLS), "clash check"),
+                ("SessionStart", None, "clash brief --hook session-start"),
+            ],
+        ),
+        InitAgent::Gemini => (
+            ".gemini/settings.json",
+            HookStyle::Nested,
+            &[
+                (
+                    "BeforeTool",
+                    Some(GEMINI_TOOLS),
+                    "clash check --hook gemini",
+                ),
+                ("AfterTool", Some(GEMINI_TOOLS), "clash check --hook gemini"),
+            ],
+        ),
+        InitAgent::Cursor => (
+            ".cursor/hooks.json",
+            HookStyle::Flat,
+            &[
+                ("beforeShellExecution", None, "clash check --hook cursor"),
+                ("afterFileEdit", None, "clash check --hook cursor"),
+            ],
+        ),
+        InitAgent::Windsurf => (
+            ".windsurf/hooks.json",
+            HookStyle::Flat,
+            &[
+                ("pre_write_code", None, "clash check --hook windsurf"),
+                ("pre_run_command", None, "clash check --hook windsurf"),
+                ("post_write_code", None, "clash check --hook windsurf"),
+            ],
+        ),
+        InitAgent::Codex => return snippet_change(&root.join("AGENTS.md")),
+        InitAgent::Copilot => {
+            return snippet_change(&root.join(".github/copilot-instructions.md"));
+        }
+    };
+
+    let path = root.join(file);
+    let old = read_optional(&path)?;
+    let mut settings: Value = match &old {
+        Some(text) if !text.trim().is_empty() => serde_json::from_str(text)
+            .map_err(|e| format!("Cannot parse {}: {}", path.display(), e))?,
+        _ => json!({}),
+    };
+    if agent == InitAgent::Cursor {
+        let object = as_object(&mut settings, &path)?;
+        object.entry("version").or_insert(json!(1));
+    }
+    for (event, matcher, command) in hooks {
+        add_hook(&mut settings, style, event, *matcher, command)
+            .map_err(|e| format!("Cannot update {}: {}", path.display(), e))?;
+    }
+    let mut new = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    new.push('\n');
+    Ok(FileChange { path, old, new })
+}
+
+/// Register a hook command for an event unless it is already there
+fn add_hook(
+    settings: &mut Value,
+    style: HookStyle,
+    event: &str,
+    matcher: Option<&str>,
+    command: &str,
+) -> Result<(), String> {
+    let hooks = settings
+        .as_object_mut()
+        .ok_or("settings are not a JSON object")?
+        .entry("hooks")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .ok_or("\"hooks\" is not a JSON object")?;
+    let entries = hooks
+        .entry(event)
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .ok_or_else(|| format!("\"hooks.{}\" is not a JSON array", event))?;
+
+    let registered = entries.iter().any(|entry| match style {
+        HookStyle::Flat => entry["command"] == command,
+        HookStyle::Nested => entry["hooks"]
+            .as_array()
+            .is_some_and(|hooks| hooks.iter().any(|h| h["command"] == command)),
+    });
+    if registered {
+        return Ok(());
+    }
+
+    entries.push(match (style, matcher) {
+        (HookStyle::Flat, _) => json!({ "command": command }),
+        (HookStyle::Nested, Some(matcher)) => json!({
+            "matcher": matcher,
+            "hooks": [{ "type": "command", "command": command }],
+        }),
+        (HookStyle::Nested, None) => json!({
+            "hooks": [{ "type": "command", "command": command }],
+        }),
+    });
+    Ok(())
+}
+
+/// An instructions file with the clash snippet added, or refreshed between
+/// its markers
+fn snippet_change(path: &Path) -> Result<FileChange, String> {
+    let old = read_optional(path)?;
+    let block = format!("{}\n{}\n{}\n", SNIPPET_START, SNIPPET, SNIPPET_END);
+    let text = old.clone().unwrap_or_default();
+
+    let new = match (text.find(SNIPPET_START), text.find(SNIPPET_END)) {
+        (Some(start), Some(end)) if start < end => {
+            let after = text[end + SNIPPET_END.len()..].trim_start_matches('\n');
+            format!("{}{}{}", &text[..start], block, after)
+        }
+        _ if text.trim().is_empty() => block,
+        _ => format!("{}\n\n{}", text.trim_end(), block),
+    };
+    Ok(FileChange {
+        path: path.to_path_buf(),
+        old,
+        new,
+    })
+}
+
+/// Read a file, treating a missing file as `None`
+fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// The settings object, or an error naming the file if it isn't one
+fn as_object<'a>(
+    settings: &'a mut Value,
+    path: &Path,
+) -> Result<&'a mut Map<String, Value>, String> {
+    settings
+        .as_object_mut()
+        .ok_or_else(|| format!("{} is not a JSON object", path.display()))
+}
+
+/// Print a unified-style diff of a file change
+fn print_diff(path: &Path, old: &str, new: &str) {
+    println!("{}", format!("--- a/{}", path.display()).bold());
+    println!("{}", format!("+++ b/{}", path.display()).bold());
+    for hunk in diff_lines(old, new) {
+        println!(
+            "{}",
+            format!(
+                "@@ -{},{} +{},{} @@",
+                hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+            )
+            .bright_cyan()
+        );
+        for line in &hunk.removed {
+            println!("{}", format!("-{}", line).red());
+        }
+        for line in &hunk.added {
+            println!("{}", format!("+{}", line).green());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_are_added_once() {
+        let mut settings = json!({ "model": "opus", "hooks": { "PreToolUse": [] } });
+        for _ in 0..2 {
+            add_hook(
+                &mut settings,
+                HookStyle::Nested,
+                "PreToolUse",
+                Some("Write"),
+                "clash check",
+            )
+            .unwrap();
+            add_hook(
+                &mut settings,
+                HookStyle::Nested,
+                "SessionStart",
+                None,
+                "clash brief",
+            )
+            .unwrap();
+        }
+        assert_eq!(settings["hooks"]["PreToolUse"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            settings["hooks"]["SessionStart"].as_array().unwrap().len(),
+            1
+        );
+        assert_eq!(settings["model"], "opus");
+
+        let mut bad = json!({ "hooks": [] });
+        assert!(
+            add_hook(
+                &mut bad,
+                HookStyle::Flat,
+                "afterFileEdit",
+                None,
+                "clash check"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn starter_config_is_valid() {
+        clash_sh::config::Config::parse(STARTER_CONFIG).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+//! Overlap detection for planned edits
+//!
+//! Compares the paths each worktree plans to touch (see
+//! [`PlannedPath`](crate::registry::PlannedPath)) against other worktrees'
+//! plans and against the files they have actually changed. This surfaces
+//! collisions before any code is written, so tasks can be reshuffled.
+
+use crate::registry::{PlannedPath, Registry};
+use crate::{Worktree, WorktreeManager};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What a planned path overlaps with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapKind {
+    /// Both worktrees plan to edit the same path
+    PlannedVsPlanned,
+    /// One worktree plans to edit a path the other has already changed
+    PlannedVsActual,
+}
+
+impl std::fmt::Display for OverlapKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OverlapKind::PlannedVsPlanned => write!(f, "planned vs planned"),
+            OverlapKind::PlannedVsActual => write!(f, "planned vs actual"),
+        }
+    }
+}
+
+/// A planned path that collides with another worktree's plan or changes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlanOverlap {
+    /// Worktree that planned the path
+    pub planned_by: String,
+    /// Worktree whose plan or changes overlap it
+    pub other: String,
+    /// The planned path or pattern
+    pub path: String,
+    /// The other side's planned path, or the file it actually changed
+    pub other_path: String,
+    pub kind: OverlapKind,
+}
+
+/// Find all plan overlaps between worktrees.
+///
+/// Plans from worktrees that no longer exist are ignored. Planned-vs-planned
+/// overlaps are reported once per pair of plans; planned-vs-actual overlaps
+/// once per changed file. Worktrees whose changes can't be listed are skipped.
+pub fn find_overlaps(worktrees: &WorktreeManager) -> Vec<PlanOverlap> {
+    let plans: Vec<PlannedPath> = Registry::open(worktrees)
+        .plans()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| worktrees.iter().any(|wt| wt.id == p.worktree))
+        .collect();
+
+    let mut overlaps = Vec::new();
+
+    for (i, a) in plans.iter().enumerate() {
+        for b in &plans[i + 1..] {
+            if a.worktree != b.worktree && a.overlaps(b) {
+                overlaps.push(PlanOverlap {
+                    planned_by: a.worktree.clone(),
+                    other: b.worktree.clone(),
+                    path: a.path.clone(),
+                    other_path: b.path.clone(),
+                    kind: OverlapKind::PlannedVsPlanned,
+                });
+            }
+        }
+    }
+
+    // Changed files depend on the merge base, so cache per (planner, other) pair
+    let mut changed: HashMap<(String, String), Vec<String>> = HashMap::new();
+    for plan in &plans {
+        let Ok(planner) = worktrees.find(&plan.worktree) else {
+            continue;
+        };
+        for other in worktrees.iter().filter(|wt| wt.id != planner.id) {
+            let files = changed
+                .entry((planner.id.clone(), other.id.clone()))
+                .or_insert_with(|| other.changed_files(planner).unwrap_or_default());
+            for file in files.iter().filter(|f| plan.covers(f)) {
+                overlaps.push(PlanOverlap {
+                    planned_by: plan.worktree.clone(),
+                    other: other.id.clone(),
+                    path: plan.path.clone(),
+                    other_path: file.clone(),
+                    kind: OverlapKind::PlannedVsActual,
+                });
+            }
+        }
+    }
+
+    overlaps
+}
+
+/// Find worktrees other than `current` that plan to edit `file`.
+pub fn planned_by_others(
+    worktrees: &WorktreeManager,
+    current: &Worktree,
+    file: &str,
+) -> Vec<PlannedPath> {
+    Registry::open(worktrees)
+        .plans()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.worktree != current.id && p.covers(file))
+        .filter(|p| worktrees.iter().any(|wt| wt.id == p.worktree))
+        .collect()
+}
@@ -0,0 +1,113 @@
+use crate::check::resolve_file_path;
+use clash_sh::WorktreeManager;
+use clash_sh::output::LedgerOutput;
+use clash_sh::registry::{EditPhase, Registry};
+use colored::Colorize;
+use std::time::Duration;
+
+/// List edits from the ledger within `since` (e.g. `10m`), optionally for one file.
+pub fn run_ledger(
+    worktrees: &WorktreeManager,
+    path: Option<&str>,
+    since: &str,
+    json: bool,
+) -> Result<(), String> {
+    let window = parse_duration(since)?;
+    let relative = path
+        .map(|p| resolve_file_path(p, worktrees).map(|(_, relative)| relative))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let entries = Registry::open(worktrees)
+        .recent_edits(relative.as_deref(), window)
+        .map_err(|e| e.to_string())?;
+
+    if json {
+        let output = LedgerOutput::new(entries);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No edits in the last {}", since);
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Edits in the last {}:", since).bright_cyan().bold()
+    );
+    for e in &entries {
+        let time = chrono::DateTime::parse_from_rfc3339(&e.timestamp)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| e.timestamp.clone());
+        let phase = match e.phase {
+            EditPhase::Attempted => "attempted".yellow(),
+            EditPhase::Completed => "completed".green(),
+        };
+        let mut line = format!(
+            "  {} {} {}",
+            time.bright_black(),
+            e.worktree.bright_blue(),
+            e.path.yellow()
+        );
+        if let Some(session) = &e.session {
+            line.push_str(&format!(" session {}", session.bright_magenta()));
+        }
+        if let Some(tool) = &e.tool {
+            line.push_str(&format!(" via {}", tool));
+        }
+        line.push_str(&format!(" {}", phase));
+        if let Some(decision) = &e.decision {
+            line.push_str(&format!(" ({})", decision));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Parse a duration like `30s`, `10m`, `2h` or `1d` (a bare number is minutes)
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", text))?;
+    let seconds = match unit {
+        "s" => 1,
+        "" | "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in '{}' (use s, m, h or d)",
+                text
+            ));
+        }
+    };
+    Ok(Duration::from_secs(number * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(300)));
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("3w").is_err());
+    }
+}
@@ -11,14 +11,20 @@
 //!
 //! - **worktree** - Worktree discovery and status tracking
 //! - **conflict** - Conflict detection using git merge-tree analysis
+//! - **registry** - Shared clash state stored in the common git directory
+//! - **intent** - Overlaps between planned edits and actual changes
+//! - **suppress** - Ignore patterns and acks that hide accepted conflicts
+//! - **config** - Repository settings from `.clash.toml`, such as the hook policy
+//! - **output** - Versioned JSON documents printed by the CLI, with JSON Schemas
 //!
 //! # Usage
 //!
 //! This library can be used in multiple ways:
 //!
 //! 1. **CLI tool** (`src/main.rs`) - Command-line interface for humans
-//! 2. **MCP server** (planned) - JSON-RPC interface for AI agents
-//! 3. **Library** - Rust code can import and use these functions directly
+//! 2. **MCP server** (`clash mcp`) - JSON-RPC interface for AI agents
+//! 3. **Daemon** (`clash daemon`) - Warm results served over a Unix socket
+//! 4. **Library** - Rust code can import and use these functions directly
 //!
 //! # Example
 //!
@@ -43,7 +49,17 @@
 //! }
 //! ```
 
+pub mod config;
+pub mod intent;
+pub mod output;
+pub mod registry;
+pub mod suppress;
 pub mod worktree;
 
+#[cfg(test)]
+mod test_repo;
+
+pub use registry::{Registry, RegistryError, RegistryResult};
+pub use suppress::Suppressions;
 pub use worktree::*;
 // Main lib updates
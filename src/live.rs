@@ -0,0 +1,147 @@
+//! Warm worktree state kept current by the file watcher
+//!
+//! Long-running commands (`clash daemon`, `clash serve`) share one
+//! [`LiveState`]: the discovered worktrees and the merge results for every
+//! pair. A background thread recomputes them shortly after files or git
+//! state change, so queries never have to rediscover or re-merge from
+//! scratch, and subscribers are notified of every new snapshot.
+
+use crate::watch::create_watcher;
+use clash_sh::{WorktreeManager, WorktreePairConflict};
+use notify::RecommendedWatcher;
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::thread;
+use std::time::Duration;
+
+/// Wait this long after the last change before recomputing (same as watch mode)
+const DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Worktrees and their pairwise merge results at one point in time
+#[derive(Debug)]
+pub struct Snapshot {
+    pub worktrees: WorktreeManager,
+    /// Each worktree's HEAD, read before `pairs` were computed
+    pub heads: Vec<(String, Option<String>)>,
+    /// Results for every worktree pair, before suppressions are applied
+    pub pairs: Vec<WorktreePairConflict>,
+    /// Incremented on every refresh
+    pub generation: u64,
+}
+
+/// Shared handle to the latest [`Snapshot`]
+#[derive(Clone)]
+pub struct LiveState {
+    snapshot: Arc<RwLock<Arc<Snapshot>>>,
+    subscribers: Arc<Mutex<Vec<Sender<Arc<Snapshot>>>>>,
+}
+
+impl LiveState {
+    /// Compute the initial snapshot and keep it current in the background
+    pub fn start(worktrees: WorktreeManager) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let (watcher, warnings) = create_watcher(&worktrees, tx.clone())?;
+        for warning in warnings {
+            eprintln!("{}", warning);
+        }
+
+        let heads = head_ids(&worktrees);
+        let pairs = worktrees.check_all_conflicts();
+        let state = Self {
+            snapshot: Arc::new(RwLock::new(Arc::new(Snapshot {
+                worktrees,
+                heads,
+                pairs,
+                generation: 0,
+            }))),
+            subscribers: Arc::default(),
+        };
+
+        let refresher = state.clone();
+        thread::spawn(move || refresher.refresh_loop(watcher, tx, rx));
+        Ok(state)
+    }
+
+    /// Get the latest snapshot
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Receive every snapshot computed from now on
+    pub fn subscribe(&self) -> Receiver<Arc<Snapshot>> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(tx);
+        rx
+    }
+
+    /// Recompute the snapshot after each burst of watcher events.
+    ///
+    /// Owns the watcher (watching stops when it is dropped) and holds on to
+    /// `tx` so the channel stays open when the watcher is replaced, which
+    /// happens whenever worktrees are added or removed.
+    fn refresh_loop(
+        &self,
+        mut _watcher: RecommendedWatcher,
+        tx: Sender<String>,
+        rx: Receiver<String>,
+    ) {
+        while rx.recv().is_ok() {
+            // Debounce: wait until events stop arriving
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            let current = self.snapshot();
+            let mut worktrees = current.worktrees.clone();
+            if let Err(e) = worktrees.refresh() {
+                eprintln!("Refresh error: {}", e);
+                continue;
+            }
+
+            let same_paths = worktrees
+                .iter()
+                .map(|wt| &wt.path)
+                .eq(current.worktrees.iter().map(|wt| &wt.path));
+            if !same_paths {
+                match create_watcher(&worktrees, tx.clone()) {
+                    Ok((new_watcher, _)) => _watcher = new_watcher,
+                    Err(e) => eprintln!("Failed to watch new worktrees: {}", e),
+                }
+            }
+
+            let heads = head_ids(&worktrees);
+            let pairs = worktrees.check_all_conflicts();
+            let snapshot = Arc::new(Snapshot {
+                worktrees,
+                heads,
+                pairs,
+                generation: current.generation + 1,
+            });
+            *self
+                .snapshot
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = snapshot.clone();
+
+            // Drop subscribers whose receiver has gone away
+            self.subscribers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .retain(|tx| tx.send(snapshot.clone()).is_ok());
+        }
+    }
+}
+
+/// HEAD commit of each worktree by id, sorted (`None` while HEAD is unborn)
+pub fn head_ids(worktrees: &WorktreeManager) -> Vec<(String, Option<String>)> {
+    let mut heads: Vec<(String, Option<String>)> = worktrees
+        .iter()
+        .map(|wt| (wt.id.clone(), wt.head_commit().map(|c| c.id)))
+        .collect();
+    heads.sort();
+    heads
+}
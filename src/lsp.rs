@@ -0,0 +1,373 @@
+//! Language Server Protocol mode
+//!
+//! `clash lsp` speaks LSP over stdio and publishes diagnostics on lines that
+//! other worktrees have also changed, so editors show cross-worktree
+//! conflicts inline. Both sides are compared as they are on disk, and
+//! diagnostics are refreshed whenever a document is opened or saved.
+//!
+//! - Lines changed on both sides (what git would report as a conflict) get
+//!   a warning naming the other worktree and branch.
+//! - Lines changed only by another worktree get an informational hint, so
+//!   you know someone else is working there before you touch them.
+//!
+//! Hovering a diagnostic shows the other side's version of the lines.
+
+use crate::check::resolve_file_path;
+use clash_sh::{Hunk, PairFileDiff, Side, Suppressions, Worktree, WorktreeManager};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+/// JSON-RPC error codes
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// LSP diagnostic severities
+const SEVERITY_WARNING: u8 = 2;
+const SEVERITY_INFORMATION: u8 = 3;
+
+/// A range of lines in the current file that another worktree also touches
+#[derive(Debug, Clone, PartialEq)]
+struct Finding {
+    /// First line (0-based, as in LSP)
+    start_line: u32,
+    /// One past the last line
+    end_line: u32,
+    severity: u8,
+    message: String,
+    /// Markdown shown on hover: the other side's version of the lines
+    hover: String,
+}
+
+/// Server state: open documents and the findings last published for them
+#[derive(Default)]
+struct Server {
+    /// Findings per open document URI
+    documents: HashMap<String, Vec<Finding>>,
+    shutdown_requested: bool,
+}
+
+/// Run the language server until the client sends `exit` or closes stdin
+pub fn run_lsp() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut stdout = io::stdout();
+    let mut server = Server::default();
+
+    while let Some(message) = read_message(&mut reader)? {
+        let Ok(message) = serde_json::from_slice::<Value>(&message) else {
+            continue;
+        };
+        if message["method"] == "exit" {
+            break;
+        }
+        for outgoing in server.handle(&message) {
+            write_message(&mut stdout, &outgoing)?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Framing
+// ============================================================================
+
+/// Read one `Content-Length` framed message, `None` at end of input
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+// ============================================================================
+// Message handling
+// ============================================================================
+
+impl Server {
+    /// Handle one message, returning the responses and notifications to send
+    fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let id = message.get("id").cloned();
+        let params = &message["params"];
+        let method = message["method"].as_str().unwrap_or_default();
+
+        let result = match method {
+            "initialize" => Some(initialize_result()),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Some(Value::Null)
+            }
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/didOpen" | "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                if !uri.is_empty() {
+                    self.documents.entry(uri.to_string()).or_default();
+                }
+                // Another worktree's files may have changed too, so refresh
+                // every open document rather than just this one
+                return self.refresh_all();
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                return vec![publish(uri, &[])];
+            }
+            _ => None,
+        };
+
+        // Notifications never get a reply
+        let Some(id) = id else {
+            return Vec::new();
+        };
+        vec![match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None if self.shutdown_requested => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": INVALID_REQUEST, "message": "server is shutting down" },
+            }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("unknown method '{}'", method) },
+            }),
+        }]
+    }
+
+    /// Recompute and publish diagnostics for every open document
+    fn refresh_all(&mut self) -> Vec<Value> {
+        let uris: Vec<String> = self.documents.keys().cloned().collect();
+        uris.into_iter()
+            .map(|uri| {
+                let findings = uri_to_path(&uri)
+                    .and_then(|path| file_findings(&path.to_string_lossy()))
+                    .unwrap_or_default();
+                let notification = publish(&uri, &findings);
+                self.documents.insert(uri, findings);
+                notification
+            })
+            .collect()
+    }
+
+    /// Show the other side's text for the findings under the cursor
+    fn hover(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let line = params["position"]["line"].as_u64().unwrap_or(u64::MAX);
+        let hovers: Vec<&str> = self
+            .documents
+            .get(uri)
+            .into_iter()
+            .flatten()
+            .filter(|f| u64::from(f.start_line) <= line && line < u64::from(f.end_line))
+            .map(|f| f.hover.as_str())
+            .collect();
+
+        if hovers.is_empty() {
+            return Value::Null;
+        }
+        json!({ "contents": { "kind": "markdown", "value": hovers.join("\n\n---\n\n") } })
+    }
+}
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": {
+                "openClose": true,
+                "change": 0,
+                "save": { "includeText": false },
+            },
+            "hoverProvider": true,
+        },
+        "serverInfo": { "name": "clash", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+/// Build a `publishDiagnostics` notification
+fn publish(uri: &str, findings: &[Finding]) -> Value {
+    let diagnostics: Vec<Value> = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "range": {
+                    "start": { "line": f.start_line, "character": 0 },
+                    "end": { "line": f.end_line, "character": 0 },
+                },
+                "severity": f.severity,
+                "source": "clash",
+                "message": f.message,
+            })
+        })
+        .collect();
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// Convert a `file://` URI to a path, decoding percent-escapes
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = percent_encoding::percent_decode_str(path)
+        .decode_utf8()
+        .ok()?
+        .into_owned();
+    // Windows URIs look like file:///C:/dir/file
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => rest.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+// ============================================================================
+// Findings
+// ============================================================================
+
+/// Compare a file against every other worktree.
+///
+/// Returns `None` if the file isn't inside a worktree. Worktrees where the
+/// diff fails (e.g. unrelated histories) are skipped, as are suppressed
+/// conflicts.
+fn file_findings(path: &str) -> Option<Vec<Finding>> {
+    let worktrees = WorktreeManager::discover_from(path).ok()?;
+    let (current, file) = resolve_file_path(path, &worktrees).ok()?;
+    let suppressions = Suppressions::load(&worktrees);
+
+    let mut findings = Vec::new();
+    for other in worktrees.iter() {
+        if other.id == current.id || suppressions.is_suppressed(current, other, &file) {
+            continue;
+        }
+        if let Ok(diff) = current.diff_file_against(other, &file, Side::Workdir) {
+            findings.extend(pair_findings(&diff, other));
+        }
+    }
+    Some(findings)
+}
+
+/// Turn one pair diff into findings in the current file's line numbers
+fn pair_findings(diff: &PairFileDiff, other: &Worktree) -> Vec<Finding> {
+    let who = format!("worktree '{}' (branch {})", other.id, other.branch);
+    let mut findings = Vec::new();
+
+    for theirs in &diff.theirs_hunks {
+        let colliding: Vec<&Hunk> = diff
+            .ours_hunks
+            .iter()
+            .filter(|ours| ours.collides_with(theirs, 0))
+            .collect();
+
+        let (start, end, severity, message) = if colliding.is_empty() {
+            // Only they changed these lines; map them into our line numbers
+            let start = diff.map_base_line(theirs.old_start);
+            (
+                start,
+                start + theirs.old_lines.max(1),
+                SEVERITY_INFORMATION,
+                format!("Also being changed in {}", who),
+            )
+        } else {
+            let start = colliding.iter().map(|h| h.new_start).min().unwrap_or(1);
+            let end = colliding
+                .iter()
+                .map(|h| h.new_start + h.new_lines.max(1))
+                .max()
+                .unwrap_or(start + 1);
+            (
+                start,
+                end,
+                SEVERITY_WARNING,
+                format!("Conflicts with changes in {}", who),
+            )
+        };
+
+        findings.push(Finding {
+            start_line: start.saturating_sub(1),
+            end_line: end.saturating_sub(1),
+            severity,
+            message,
+            hover: hover_text(theirs, other),
+        });
+    }
+    findings
+}
+
+fn hover_text(theirs: &Hunk, other: &Worktree) -> String {
+    let header = format!("**{}** (worktree `{}`)", other.branch, other.id);
+    if theirs.added.is_empty() {
+        format!("{} deletes these lines", header)
+    } else {
+        format!("{} has:\n\n```\n{}\n```", header, theirs.added.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clash_sh::{WorktreeStatus, diff_lines};
+
+    fn diff(base: &str, ours: &str, theirs: &str) -> PairFileDiff {
+        PairFileDiff {
+            path: "f.txt".to_string(),
+            base_commit: String::new(),
+            base: Some(base.to_string()),
+            ours: Some(ours.to_string()),
+            theirs: Some(theirs.to_string()),
+            ours_hunks: diff_lines(base, ours),
+            theirs_hunks: diff_lines(base, theirs),
+        }
+    }
+
+    #[test]
+    fn findings_use_current_file_line_numbers() {
+        let other = Worktree {
+            id: "agent-3".to_string(),
+            path: PathBuf::from("/repo-agent-3"),
+            branch: "feat".to_string(),
+            status: WorktreeStatus::Clean,
+        };
+        // We inserted two lines at the top and edited line 5; they edited
+        // lines 5 and 8 of the base
+        let base = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let ours = "a\nb\n1\n2\n3\n4\nX\n6\n7\n8\n";
+        let theirs = "1\n2\n3\n4\nY\n6\n7\nZ\n";
+
+        let findings = pair_findings(&diff(base, ours, theirs), &other);
+        assert_eq!(findings.len(), 2);
+
+        assert_eq!(findings[0].severity, SEVERITY_WARNING);
+        assert_eq!((findings[0].start_line, findings[0].end_line), (6, 7));
+        assert!(findings[0].hover.contains("Y"));
+
+        assert_eq!(findings[1].severity, SEVERITY_INFORMATION);
+        assert_eq!((findings[1].start_line, findings[1].end_line), (9, 10));
+    }
+}
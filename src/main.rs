@@ -2,8 +2,28 @@ use clap::{Parser, Subcommand};
 use clash_sh::WorktreeManager;
 use colored::control;
 
+mod ack;
+mod batches;
+mod brief;
 mod check;
+mod claim;
+mod daemon;
+mod format;
+mod githooks;
+mod graph;
+mod init;
+mod ledger;
+mod live;
+mod lsp;
+mod mcp;
+mod msg;
+mod plan;
+mod report;
+mod schema;
+mod serve;
 mod status;
+#[cfg(test)]
+mod test_repo;
 mod watch;
 
 #[derive(Parser)]
@@ -19,11 +39,58 @@ struct Cli {
 enum Commands {
     /// Show potential conflicts between worktrees (supports --json for AI agents)
     Status {
+        #[arg(
+            long,
+            conflicts_with = "format",
+            help = "Output results as JSON (same as --format json)"
+        )]
+        json: bool,
+        #[arg(long, value_enum, default_value = "text", help = "Output format")]
+        format: format::OutputFormat,
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
+    },
+    /// Write a Markdown or HTML conflict report for PR comments and CI artifacts
+    Report {
+        #[arg(long, value_enum, default_value = "markdown", help = "Report format")]
+        format: report::ReportFormat,
+        #[arg(
+            short,
+            long,
+            value_name = "FILE",
+            help = "Write the report to a file instead of stdout"
+        )]
+        output: Option<std::path::PathBuf>,
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
+    },
+    /// Export the conflict graph (worktrees as nodes, conflicts as edges) as DOT or Mermaid
+    Graph {
+        #[arg(long, value_enum, default_value = "dot", help = "Graph format")]
+        format: graph::GraphFormat,
+        #[arg(long, help = "Group worktrees connected through conflicts")]
+        cluster: bool,
+        #[arg(
+            short,
+            long,
+            value_name = "FILE",
+            help = "Write the graph to a file instead of stdout"
+        )]
+        output: Option<std::path::PathBuf>,
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
+    },
+    /// Group worktrees into the fewest batches that can be merged together without conflicts
+    Batches {
         #[arg(long, help = "Output results as JSON")]
         json: bool,
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
     },
     /// Watch for conflicts in real-time with interactive TUI
     Watch {
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
         // TODO: Add --debounce flag for handling rapid file changes
         // When many files change quickly (e.g., during git rebase),
         // we should wait for changes to settle before rechecking conflicts,
@@ -33,7 +100,230 @@ enum Commands {
     Check {
         /// File path to check (reads from hook stdin if omitted)
         path: Option<String>,
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
+        #[arg(
+            long,
+            value_enum,
+            conflicts_with = "path",
+            help = "Agent whose hook format to read and answer (detected from stdin if omitted)"
+        )]
+        hook: Option<check::Agent>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["path", "hook"],
+            help = "Check every file touched by a unified diff or apply_patch patch (- for stdin)"
+        )]
+        patch: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "json",
+            conflicts_with = "hook",
+            help = "Output format"
+        )]
+        format: format::OutputFormat,
+    },
+    /// Summarize other worktrees' changes and conflicts, or answer a session hook with it
+    Brief {
+        #[arg(
+            long,
+            value_enum,
+            help = "Read a hook payload from stdin and answer with the briefing as context"
+        )]
+        hook: Option<brief::BriefHook>,
+        #[arg(long, help = "Include ignored and acknowledged conflicts")]
+        all: bool,
+    },
+    /// Acknowledge a conflict so it is hidden until either side changes the file
+    Ack {
+        /// First worktree (id or branch)
+        wt1: String,
+        /// Second worktree (id or branch)
+        wt2: String,
+        /// Repo-relative path of the conflicting file
+        path: String,
+    },
+    /// Claim paths for the current worktree so other agents' hooks refuse to edit them
+    Claim {
+        /// Glob patterns to claim (lists all claims if omitted)
+        patterns: Vec<String>,
+        #[arg(long, help = "Agent session holding the claim")]
+        session: Option<String>,
+    },
+    /// Release claims held by the current worktree
+    Release {
+        /// Patterns to release (releases all if omitted)
+        patterns: Vec<String>,
+        #[arg(long, help = "Only release claims held by this session")]
+        session: Option<String>,
+    },
+    /// List recent edits reported by agent hooks, optionally for one file
+    Ledger {
+        /// File to list edits for (all files if omitted)
+        path: Option<String>,
+        #[arg(
+            long,
+            default_value = "10m",
+            help = "How far back to look (e.g. 30s, 10m, 2h, 1d)"
+        )]
+        since: String,
+        #[arg(long, help = "Output results as JSON")]
+        json: bool,
+    },
+    /// Leave messages for agents in other worktrees and read theirs
+    Msg {
+        #[command(subcommand)]
+        action: MsgAction,
+    },
+    /// Set up clash for this repository: agent hooks, instructions, config and git hooks
+    Init {
+        #[arg(
+            long = "agent",
+            value_enum,
+            value_delimiter = ',',
+            help = "Agents to set up (detected from their config files if omitted)"
+        )]
+        agents: Vec<init::InitAgent>,
+        #[arg(
+            long,
+            help = "Also install the pre-commit, pre-push and post-merge git hooks"
+        )]
+        git_hooks: bool,
+        #[arg(long, help = "Print the changes as diffs without writing anything")]
+        dry_run: bool,
+    },
+    /// Manage the git hooks that check commits and pushes across worktrees
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+    /// Check staged files against other worktrees (run by the pre-commit hook)
+    Precommit {
+        #[command(flatten)]
+        mode: HookModeArgs,
+    },
+    /// Check pushed commits against the remote's default branch and other worktrees (run by the pre-push hook)
+    Prepush {
+        /// Remote name, as passed to the pre-push hook
+        remote: Option<String>,
+        /// Remote URL, as passed to the pre-push hook
+        url: Option<String>,
+        #[command(flatten)]
+        mode: HookModeArgs,
+    },
+    /// Print the JSON Schema of a JSON document clash produces
+    Schema {
+        #[arg(value_enum)]
+        document: schema::SchemaDocument,
+    },
+    /// Run an MCP (Model Context Protocol) server on stdio exposing clash as tools
+    Mcp {},
+    /// Run a language server on stdio that shows cross-worktree conflicts as diagnostics
+    Lsp {},
+    /// Declare files the current worktree plans to edit, to catch overlaps early
+    Plan {
+        #[command(subcommand)]
+        action: PlanAction,
+    },
+    /// Keep conflict results warm and serve them to check/status over a Unix socket
+    Daemon {},
+    /// Serve worktrees, conflicts and file checks as a local HTTP/JSON API with live events
+    Serve {
+        #[arg(
+            long,
+            default_value_t = 7420,
+            help = "Port to listen on (localhost only)"
+        )]
+        port: u16,
+    },
+    /// Open an interactive UI: the terminal UI, or a live web dashboard with --web
+    Ui {
+        #[arg(
+            long,
+            help = "Serve a web dashboard on localhost and open it in a browser"
+        )]
+        web: bool,
+        #[arg(long, default_value_t = 7420, help = "Port for the web dashboard")]
+        port: u16,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlanAction {
+    /// Record paths (or glob patterns) this worktree plans to edit
+    Add {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Remove planned paths (removes all if omitted)
+    Remove { paths: Vec<String> },
+    /// List planned paths across worktrees and their overlaps
+    List {
+        #[arg(long, help = "Output results as JSON")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksAction {
+    /// Write pre-commit, pre-push and post-merge hooks (respects core.hooksPath)
+    Install {
+        #[arg(
+            long,
+            help = "Move hooks not written by clash to <hook>.pre-clash and run them first"
+        )]
+        force: bool,
+    },
+}
+
+/// Overrides for `[git_hooks] mode` in .clash.toml
+#[derive(clap::Args)]
+struct HookModeArgs {
+    #[arg(long, conflicts_with = "warn", help = "Fail when conflicts are found")]
+    block: bool,
+    #[arg(long, help = "Only report conflicts")]
+    warn: bool,
+}
+
+impl HookModeArgs {
+    fn mode(&self) -> Option<clash_sh::config::GitHookMode> {
+        use clash_sh::config::GitHookMode;
+        match (self.block, self.warn) {
+            (true, _) => Some(GitHookMode::Block),
+            (_, true) => Some(GitHookMode::Warn),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum MsgAction {
+    /// Send a message to every other worktree (or one worktree, or a path)
+    Send {
+        /// Message text
+        body: String,
+        #[arg(
+            long,
+            conflicts_with = "path",
+            help = "Worktree (id or branch) to send to"
+        )]
+        to: Option<String>,
+        #[arg(long, help = "Send to whoever edits paths matching this glob")]
+        path: Option<String>,
+        #[arg(long, help = "Agent session sending the message")]
+        session: Option<String>,
     },
+    /// List unread messages for the current worktree
+    List {
+        #[arg(long, help = "Include acknowledged messages")]
+        all: bool,
+        #[arg(long, help = "Output results as JSON")]
+        json: bool,
+    },
+    /// Mark messages as read for the current worktree (all if omitted)
+    Ack { ids: Vec<u64> },
 }
 
 fn main() {
@@ -44,18 +334,72 @@ fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::Status { json }) => match WorktreeManager::discover() {
+        Some(Commands::Status { json, format, all }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                let format = if json {
+                    format::OutputFormat::Json
+                } else {
+                    format
+                };
+                status::run_status(&worktrees, format, all);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Report {
+            format,
+            output,
+            all,
+        }) => match WorktreeManager::discover() {
             Ok(worktrees) => {
-                status::run_status(&worktrees, json);
+                if let Err(e) = report::run_report(&worktrees, format, all, output.as_deref()) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
             }
             Err(e) => {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         },
-        Some(Commands::Watch {}) => match WorktreeManager::discover() {
+        Some(Commands::Graph {
+            format,
+            cluster,
+            output,
+            all,
+        }) => match WorktreeManager::discover() {
             Ok(worktrees) => {
-                if let Err(e) = watch::run_watch_mode(worktrees) {
+                if let Err(e) =
+                    graph::run_graph(&worktrees, format, cluster, all, output.as_deref())
+                {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Batches { json, all }) => match WorktreeManager::discover() {
+            Ok(worktrees) => match batches::run_batches(&worktrees, all, json) {
+                Ok(0) => {}
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Watch { all }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = watch::run_watch_mode(worktrees, all) {
                     eprintln!("Error running watch mode: {}", e);
                     std::process::exit(1);
                 }
@@ -65,9 +409,246 @@ fn main() {
                 std::process::exit(1);
             }
         },
-        Some(Commands::Check { path }) => match check::run_check(path.as_deref()) {
-            Ok(true) if path.is_some() => std::process::exit(2),
-            Ok(_) => {}
+        Some(Commands::Check {
+            path,
+            all,
+            hook,
+            patch,
+            format,
+        }) => {
+            let result = match patch {
+                Some(patch) => check::run_check_patch(&patch, all, format),
+                None => check::run_check(path.as_deref(), all, hook, format),
+            };
+            match result {
+                Ok(0) => {}
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Commands::Brief { hook, all }) => {
+            if let Err(e) = brief::run_brief(hook, all) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Ack { wt1, wt2, path }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = ack::run_ack(&worktrees, &wt1, &wt2, &path) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Claim { patterns, session }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = claim::run_claim(&worktrees, &patterns, session.as_deref()) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Release { patterns, session }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = claim::run_release(&worktrees, &patterns, session.as_deref()) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Ledger { path, since, json }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = ledger::run_ledger(&worktrees, path.as_deref(), &since, json) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Plan { action }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                let result = match action {
+                    PlanAction::Add { paths } => plan::run_plan_add(&worktrees, &paths),
+                    PlanAction::Remove { paths } => plan::run_plan_remove(&worktrees, &paths),
+                    PlanAction::List { json } => plan::run_plan_list(&worktrees, json),
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Msg { action }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                let result = match action {
+                    MsgAction::Send {
+                        body,
+                        to,
+                        path,
+                        session,
+                    } => msg::run_msg_send(
+                        &worktrees,
+                        &body,
+                        to.as_deref(),
+                        path.as_deref(),
+                        session.as_deref(),
+                    ),
+                    MsgAction::List { all, json } => msg::run_msg_list(&worktrees, all, json),
+                    MsgAction::Ack { ids } => msg::run_msg_ack(&worktrees, &ids),
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Init {
+            agents,
+            git_hooks,
+            dry_run,
+        }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = init::run_init(&worktrees, &agents, git_hooks, dry_run) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Hooks { action }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                let result = match action {
+                    HooksAction::Install { force } => {
+                        githooks::run_hooks_install(&worktrees, force)
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Precommit { mode }) => match WorktreeManager::discover() {
+            Ok(worktrees) => match githooks::run_precommit(&worktrees, mode.mode()) {
+                Ok(0) => {}
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Prepush {
+            remote,
+            url: _,
+            mode,
+        }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                match githooks::run_prepush(&worktrees, remote.as_deref(), mode.mode()) {
+                    Ok(0) => {}
+                    Ok(code) => std::process::exit(code),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Schema { document }) => {
+            if let Err(e) = schema::run_schema(document) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Mcp {}) => {
+            if let Err(e) = mcp::run_mcp() {
+                eprintln!("Error running MCP server: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Lsp {}) => {
+            if let Err(e) = lsp::run_lsp() {
+                eprintln!("Error running language server: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Daemon {}) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = daemon::run_daemon(worktrees) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Serve { port }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                if let Err(e) = serve::run_serve(worktrees, port, false) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Ui { web, port }) => match WorktreeManager::discover() {
+            Ok(worktrees) => {
+                let result = if web {
+                    serve::run_serve(worktrees, port, true)
+                } else {
+                    watch::run_watch_mode(worktrees, false).map_err(|e| e.to_string())
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
             Err(e) => {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
@@ -0,0 +1,334 @@
+//! MCP (Model Context Protocol) server over stdio
+//!
+//! Speaks newline-delimited JSON-RPC 2.0 on stdin/stdout and exposes clash's
+//! checks as tools, so agents without shell hooks can query clash natively.
+//! Every tool call rediscovers worktrees, so results are always current.
+
+use crate::check::{self, resolve_file_path};
+use crate::status::{build_status_output, check_pairs};
+use clash_sh::output::{
+    ExplainOutput, Explanation, FileDivergence, SCHEMA_VERSION, WorktreesOutput,
+};
+use clash_sh::{DivergenceKind, Side, Suppressions, WorktreeManager};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+
+/// Protocol versions this server understands, newest first
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Run the MCP server until stdin closes
+pub fn run_mcp() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&message),
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Handle one JSON-RPC message, returning the response (`None` for notifications)
+fn handle_message(message: &Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let Some(method) = message["method"].as_str() else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "missing method",
+        ));
+    };
+
+    let result = match method {
+        "initialize" => Ok(initialize_result(&message["params"])),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(&message["params"]),
+        // Notifications (e.g. notifications/initialized) need no reply
+        _ if id.is_none() => return None,
+        _ => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+    };
+
+    // A request without an id is a notification, even for known methods
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, msg)) => error_response(id, code, &msg),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Reply to `initialize`, agreeing on the client's protocol version when we support it
+fn initialize_result(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| **v == requested)
+        .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "clash", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Use check_file before editing a file to see whether other \
+            worktrees conflict with it or are actively changing it. Use status for \
+            an overview of all worktree pairs, and explain_conflict to see the \
+            conflicting hunks.",
+    })
+}
+
+// ============================================================================
+// Tools
+// ============================================================================
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "status",
+            "description": "List all worktrees and the files that would conflict between each pair if merged.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Any path inside the repository (defaults to the server's working directory)" },
+                    "all": { "type": "boolean", "description": "Include ignored and acknowledged conflicts" }
+                }
+            }
+        },
+        {
+            "name": "check_file",
+            "description": "Check one file for merge conflicts, active uncommitted changes, claims and planned edits in other worktrees. Call this before editing a file.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path, absolute or relative to the server's working directory" },
+                    "session": { "type": "string", "description": "Calling agent's session id, so its own claims are not reported" },
+                    "all": { "type": "boolean", "description": "Include ignored and acknowledged conflicts" },
+                    "content": { "type": "string", "description": "The file's full content after the edit you are about to make; each conflict then reports whether this edit introduces a merge conflict" }
+                },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "list_worktrees",
+            "description": "List the worktrees of the repository with their branch and clean/dirty status.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Any path inside the repository (defaults to the server's working directory)" }
+                }
+            }
+        },
+        {
+            "name": "explain_conflict",
+            "description": "Show how a file diverged between its worktree and other worktrees: the kind of divergence, each side's hunks against the merge base, and the regions where they collide. Worktrees that cannot be compared are listed with an error.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path, absolute or relative to the server's working directory" },
+                    "other": { "type": "string", "description": "Only compare against this worktree (id or branch)" }
+                },
+                "required": ["path"]
+            }
+        }
+    ])
+}
+
+/// Dispatch a `tools/call` request.
+///
+/// Tool failures are reported inside the result with `isError`, as MCP
+/// requires; only malformed requests become JSON-RPC errors.
+fn call_tool(params: &Value) -> Result<Value, (i64, String)> {
+    let name = params["name"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "missing tool name".to_string()))?;
+    let args = &params["arguments"];
+
+    let outcome = match name {
+        "status" => tool_status(args),
+        "check_file" => tool_check_file(args),
+        "list_worktrees" => tool_list_worktrees(args),
+        "explain_conflict" => tool_explain_conflict(args),
+        _ => return Err((INVALID_PARAMS, format!("unknown tool '{}'", name))),
+    };
+
+    Ok(match outcome {
+        Ok(value) => json!({
+            "content": [{
+                "type": "text",
+                "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+            }],
+            "structuredContent": value,
+            "isError": false,
+        }),
+        Err(msg) => json!({
+            "content": [{ "type": "text", "text": msg }],
+            "isError": true,
+        }),
+    })
+}
+
+fn discover(args: &Value) -> Result<WorktreeManager, String> {
+    let path = args["path"].as_str().unwrap_or(".");
+    WorktreeManager::discover_from(path).map_err(|e| e.to_string())
+}
+
+fn required_path(args: &Value) -> Result<&str, String> {
+    args["path"]
+        .as_str()
+        .ok_or_else(|| "missing required argument 'path'".to_string())
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+fn tool_status(args: &Value) -> Result<Value, String> {
+    let worktrees = discover(args)?;
+    let all = args["all"].as_bool().unwrap_or(false);
+    to_value(&build_status_output(
+        &worktrees,
+        check_pairs(&worktrees, all).0,
+    ))
+}
+
+fn tool_check_file(args: &Value) -> Result<Value, String> {
+    let path = required_path(args)?;
+    let worktrees = WorktreeManager::discover_from(path).map_err(|e| e.to_string())?;
+    let suppressions = if args["all"].as_bool().unwrap_or(false) {
+        Suppressions::none()
+    } else {
+        Suppressions::load(&worktrees)
+    };
+    let output = check::check_file(
+        &worktrees,
+        path,
+        args["session"].as_str(),
+        &suppressions,
+        None,
+        args["content"]
+            .as_str()
+            .map(|content| check::Proposal {
+                content: content.to_string(),
+                lines: None,
+            })
+            .as_ref(),
+    )
+    .map_err(|e| e.to_string())?;
+    to_value(&output)
+}
+
+fn tool_list_worktrees(args: &Value) -> Result<Value, String> {
+    let worktrees = discover(args)?;
+    to_value(&WorktreesOutput::new(worktrees.all().to_vec()))
+}
+
+fn tool_explain_conflict(args: &Value) -> Result<Value, String> {
+    let path = required_path(args)?;
+    let worktrees = WorktreeManager::discover_from(path).map_err(|e| e.to_string())?;
+    let (current, file) = resolve_file_path(path, &worktrees).map_err(|e| e.to_string())?;
+
+    let others: Vec<_> = match args["other"].as_str() {
+        Some(name) => {
+            let other = worktrees.find(name).map_err(|e| e.to_string())?;
+            if other.id == current.id {
+                return Err(format!("'{}' is the worktree containing {}", name, path));
+            }
+            vec![other]
+        }
+        None => worktrees.iter().filter(|wt| wt.id != current.id).collect(),
+    };
+
+    let mut explanations = Vec::new();
+    for other in others {
+        // A worktree that can't be diffed gets an error of its own, like a
+        // pair in `status`, so the others are still explained
+        let checked = current
+            .diff_file_against(other, &file, Side::Head)
+            .and_then(|diff| Ok((diff, current.conflicts_with(other)?.contains(&file))));
+        let (diff, has_merge_conflict) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                explanations.push(Explanation {
+                    worktree: other.id.clone(),
+                    branch: other.branch.clone(),
+                    has_merge_conflict: None,
+                    base_commit: None,
+                    divergence: FileDivergence {
+                        path: file.clone(),
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                });
+                continue;
+            }
+        };
+
+        // Without an explicit target, only report worktrees where the file diverged
+        let kind = diff.kind();
+        if args["other"].is_null() && kind == DivergenceKind::None {
+            continue;
+        }
+
+        explanations.push(Explanation {
+            worktree: other.id.clone(),
+            branch: other.branch.clone(),
+            has_merge_conflict: Some(has_merge_conflict),
+            base_commit: Some(diff.base_commit.clone()),
+            divergence: FileDivergence::from(diff),
+        });
+    }
+
+    to_value(&ExplainOutput {
+        schema_version: SCHEMA_VERSION,
+        file,
+        current_worktree: current.id.clone(),
+        current_branch: current.branch.clone(),
+        explanations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    #[test]
+    fn explain_conflict_rejects_the_worktree_of_the_file() {
+        let repo = TestRepo::new(&[("a.txt", "a\n")]);
+        repo.add_worktree("feature");
+        let path = repo.path("feature").join("a.txt");
+        let explain = |other: &str| tool_explain_conflict(&json!({ "path": path, "other": other }));
+        assert!(
+            explain("feature")
+                .unwrap_err()
+                .contains("is the worktree containing")
+        );
+        assert!(explain("main").is_ok());
+    }
+}
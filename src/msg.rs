@@ -0,0 +1,131 @@
+use clash_sh::WorktreeManager;
+use clash_sh::output::MessagesOutput;
+use clash_sh::registry::{Message, Recipient, Registry};
+use colored::Colorize;
+
+/// Send a message from the current worktree to a worktree, to whoever edits
+/// paths matching a glob, or to everyone.
+pub fn run_msg_send(
+    worktrees: &WorktreeManager,
+    body: &str,
+    to: Option<&str>,
+    path: Option<&str>,
+    session: Option<&str>,
+) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+
+    let recipient = match (to, path) {
+        (Some(to), _) => {
+            let wt = worktrees.find(to).map_err(|e| e.to_string())?;
+            if wt.id == current.id {
+                return Err("cannot send a message to the current worktree".into());
+            }
+            Recipient::Worktree(wt.id.clone())
+        }
+        (None, Some(path)) => {
+            clash_sh::registry::validate_pattern(path)
+                .map_err(|e| format!("invalid path '{}': {}", path, e))?;
+            Recipient::Path(path.to_string())
+        }
+        (None, None) => Recipient::All,
+    };
+
+    let id = Registry::open(worktrees)
+        .post_message(Message::new(&current.id, session, recipient, body))
+        .map_err(|e| e.to_string())?;
+    println!(
+        "Sent message {} from {}",
+        format!("#{}", id).bright_green().bold(),
+        current.id.bright_blue()
+    );
+    Ok(())
+}
+
+/// List messages for the current worktree (unread only unless `all`).
+pub fn run_msg_list(worktrees: &WorktreeManager, all: bool, json: bool) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+
+    let messages: Vec<Message> = Registry::open(worktrees)
+        .messages()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|m| m.is_for(&current.id) && (all || !m.is_acked_by(&current.id)))
+        .collect();
+
+    if json {
+        let output = MessagesOutput::new(messages);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+
+    if messages.is_empty() {
+        println!("No {}messages", if all { "" } else { "unread " });
+        return Ok(());
+    }
+
+    println!("{}", "Messages:".bright_cyan().bold());
+    for m in &messages {
+        let read = if m.is_acked_by(&current.id) {
+            " (read)".bright_black().to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} {} {}: {}{}",
+            format!("#{}", m.id).bright_green(),
+            m.from.bright_blue(),
+            describe_recipient(&m.to).bright_black(),
+            m.body,
+            read
+        );
+    }
+    Ok(())
+}
+
+/// Acknowledge messages for the current worktree (all unread ones if no ids
+/// are given).
+pub fn run_msg_ack(worktrees: &WorktreeManager, ids: &[u64]) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+    let registry = Registry::open(worktrees);
+
+    let ids: Vec<u64> = if ids.is_empty() {
+        registry
+            .messages()
+            .map_err(|e| e.to_string())?
+            .iter()
+            .filter(|m| m.is_for(&current.id))
+            .map(|m| m.id)
+            .collect()
+    } else {
+        ids.to_vec()
+    };
+    let acked = registry
+        .ack_messages(&current.id, &ids)
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Acknowledged {} message{} for {}",
+        acked.to_string().bright_green().bold(),
+        if acked == 1 { "" } else { "s" },
+        current.id.bright_blue()
+    );
+    Ok(())
+}
+
+/// Short description of who a message is for, e.g. `to all`
+pub fn describe_recipient(to: &Recipient) -> String {
+    match to {
+        Recipient::All => "to all".to_string(),
+        Recipient::Worktree(id) => format!("to {}", id),
+        Recipient::Path(pattern) => format!("about {}", pattern),
+    }
+}
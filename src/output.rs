@@ -0,0 +1,491 @@
+//! Machine-readable output of the `clash` commands
+//!
+//! Every JSON document clash produces is one of the types here: `--json`
+//! on status, check, ledger, msg, plan and batches, the generic hook
+//! response, the HTTP API and its events, the MCP tools' structured content
+//! and the daemon's replies. Each document carries a `schema_version`,
+//! bumped whenever a field is removed, renamed or changes meaning; adding an
+//! optional field doesn't bump it. `clash schema <document>` prints the JSON
+//! Schema of a document.
+
+use crate::intent::PlanOverlap;
+use crate::registry::{Claim, LedgerEntry, Message, PlannedPath};
+use crate::{
+    CommitInfo, ConflictRegion, DivergenceKind, Hunk, PairFileDiff, SequenceConflict, Worktree,
+    WorktreePairConflict, WorktreeStatus,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Version of the JSON documents described by this module
+pub const SCHEMA_VERSION: u32 = 1;
+
+// ============================================================================
+// clash status
+// ============================================================================
+
+/// `clash status --json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StatusOutput {
+    pub schema_version: u32,
+    pub worktrees: Vec<WorktreeInfo>,
+    /// Pairs with conflicting files or a failed check
+    pub conflicts: Vec<ConflictInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan_overlaps: Vec<PlanOverlap>,
+}
+
+impl StatusOutput {
+    pub fn new(
+        worktrees: Vec<WorktreeInfo>,
+        conflicts: Vec<ConflictInfo>,
+        plan_overlaps: Vec<PlanOverlap>,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            worktrees,
+            conflicts,
+            plan_overlaps,
+        }
+    }
+}
+
+/// Worktree information for JSON output (simplified from full Worktree struct)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorktreeInfo {
+    pub id: String,
+    pub path: String,
+    pub branch: String,
+    pub status: WorktreeStatus,
+}
+
+/// Conflict information for JSON output (references worktrees by ID)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConflictInfo {
+    pub wt1_id: String,
+    pub wt2_id: String,
+    pub conflicting_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<WorktreePairConflict> for ConflictInfo {
+    fn from(pair: WorktreePairConflict) -> Self {
+        Self {
+            wt1_id: pair.wt1.id,
+            wt2_id: pair.wt2.id,
+            conflicting_files: pair.conflicting_files,
+            error: pair.error,
+        }
+    }
+}
+
+// ============================================================================
+// clash check
+// ============================================================================
+
+/// `clash check --json`: what other worktrees are doing to one file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CheckOutput {
+    pub schema_version: u32,
+    /// Path of the file, relative to the worktree root
+    pub file: String,
+    pub current_worktree: String,
+    pub current_branch: String,
+    pub conflicts: Vec<FileConflict>,
+    /// Conflicts in other parts of the file, far from the lines the proposed
+    /// edit touches; reported but not worth interrupting the edit for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distant_conflicts: Vec<FileConflict>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub claimed_by: Vec<ClaimInfo>,
+    /// Worktrees that plan to edit this file (via `clash plan add`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub planned_by: Vec<String>,
+    /// Other agents' latest edits of this file within the last few minutes,
+    /// from the edit ledger; these show up before any diff does
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_edits: Vec<LedgerEntry>,
+    /// Unread messages from other worktrees that concern this file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<Message>,
+}
+
+impl CheckOutput {
+    /// Whether anything was found that should make the caller pause
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty() || !self.claimed_by.is_empty() || !self.planned_by.is_empty()
+    }
+}
+
+/// Another worktree that changes the checked file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileConflict {
+    pub worktree: String,
+    pub branch: String,
+    pub has_merge_conflict: bool,
+    pub has_active_changes: bool,
+    /// Whether the proposed edit would create a merge conflict that doesn't
+    /// exist yet (only set when the hook payload carried the new content)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub introduces_conflict: Option<bool>,
+}
+
+/// A claim held by another worktree that covers the checked file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClaimInfo {
+    pub worktree: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    pub pattern: String,
+}
+
+/// `clash check --patch --json`: results for every file a patch touches
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PatchCheckOutput {
+    pub schema_version: u32,
+    pub files: Vec<CheckOutput>,
+}
+
+impl PatchCheckOutput {
+    pub fn new(files: Vec<CheckOutput>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            files,
+        }
+    }
+}
+
+/// What clash thinks the agent should do with the edit
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    Ask,
+    Deny,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Allow => "allow",
+            Decision::Ask => "ask",
+            Decision::Deny => "deny",
+        }
+    }
+}
+
+/// `clash check --hook generic`: the decision on a pre-edit hook payload
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HookOutput {
+    pub schema_version: u32,
+    pub decision: Decision,
+    /// Why the edit should be reconsidered, for the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// What the other worktrees changed, for the agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Results for every file the tool will modify
+    pub checks: Vec<CheckOutput>,
+}
+
+// ============================================================================
+// Coordination
+// ============================================================================
+
+/// `clash ledger --json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LedgerOutput {
+    pub schema_version: u32,
+    pub edits: Vec<LedgerEntry>,
+}
+
+impl LedgerOutput {
+    pub fn new(edits: Vec<LedgerEntry>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            edits,
+        }
+    }
+}
+
+/// `clash msg list --json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MessagesOutput {
+    pub schema_version: u32,
+    pub messages: Vec<Message>,
+}
+
+impl MessagesOutput {
+    pub fn new(messages: Vec<Message>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            messages,
+        }
+    }
+}
+
+/// `clash plan list --json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlansOutput {
+    pub schema_version: u32,
+    pub plans: Vec<PlannedPath>,
+    pub overlaps: Vec<PlanOverlap>,
+}
+
+impl PlansOutput {
+    pub fn new(plans: Vec<PlannedPath>, overlaps: Vec<PlanOverlap>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            plans,
+            overlaps,
+        }
+    }
+}
+
+// ============================================================================
+// clash batches
+// ============================================================================
+
+/// `clash batches --json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchesOutput {
+    pub schema_version: u32,
+    /// Worktree the batches are merged onto, if there is a main worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub batches: Vec<Batch>,
+}
+
+impl BatchesOutput {
+    pub fn new(target: Option<String>, batches: Vec<Batch>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            target,
+            batches,
+        }
+    }
+}
+
+/// A group of worktrees that can land together
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Batch {
+    pub worktrees: Vec<String>,
+    /// Whether merging the batch in sequence onto the main worktree is clean
+    pub verified: bool,
+    /// The merge that failed, if verification failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<SequenceConflict>,
+    /// Why the batch couldn't be verified at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// ============================================================================
+// Worktrees and pairs (HTTP API, MCP server)
+// ============================================================================
+
+/// Every worktree (`/api/worktrees`, MCP `list_worktrees`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorktreesOutput {
+    pub schema_version: u32,
+    pub worktrees: Vec<Worktree>,
+}
+
+impl WorktreesOutput {
+    pub fn new(worktrees: Vec<Worktree>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            worktrees,
+        }
+    }
+}
+
+/// One worktree in detail (`/api/worktree`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorktreeDetail {
+    pub schema_version: u32,
+    pub worktree: Worktree,
+    pub head: Option<CommitInfo>,
+    pub uncommitted_files: BTreeSet<String>,
+    /// Claims held by the worktree
+    pub claims: Vec<Claim>,
+    /// Paths the worktree plans to edit
+    pub plans: Vec<PlannedPath>,
+}
+
+/// Every worktree pair with its conflicting files (`/api/matrix`, and the
+/// `matrix` event of `/api/events`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MatrixOutput {
+    pub schema_version: u32,
+    /// Increases every time the results are refreshed
+    pub generation: u64,
+    /// Worktree ids, in discovery order
+    pub worktrees: Vec<String>,
+    /// One entry per pair, including pairs without conflicts
+    pub pairs: Vec<ConflictInfo>,
+    /// Number of conflicting files hidden by .clashignore or acks
+    pub suppressed: usize,
+}
+
+/// How the matrix changed (the `change` event of `/api/events`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChangeEvent {
+    pub schema_version: u32,
+    pub generation: u64,
+    /// Worktree ids, in discovery order
+    pub worktrees: Vec<String>,
+    pub changes: Vec<PairChange>,
+}
+
+/// How one pair's conflicts changed between two matrices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PairChange {
+    pub wt1_id: String,
+    pub wt2_id: String,
+    /// Files that started conflicting
+    pub added: Vec<String>,
+    /// Files that no longer conflict
+    pub resolved: Vec<String>,
+    /// Detection error, if the pair can no longer be checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A pair's conflicting files with both sides' hunks (`/api/pair`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PairOutput {
+    pub schema_version: u32,
+    pub wt1: String,
+    pub wt2: String,
+    pub files: Vec<FileDivergence>,
+    /// Detection error, if the pair couldn't be checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How one file diverged between two worktrees, against their merge base
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileDivergence {
+    pub path: String,
+    /// Unset if the file couldn't be diffed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<DivergenceKind>,
+    /// Where both sides changed overlapping merge-base lines
+    #[serde(default)]
+    pub regions: Vec<ConflictRegion>,
+    #[serde(default)]
+    pub ours_hunks: Vec<Hunk>,
+    #[serde(default)]
+    pub theirs_hunks: Vec<Hunk>,
+    /// Why the file couldn't be diffed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<PairFileDiff> for FileDivergence {
+    fn from(diff: PairFileDiff) -> Self {
+        Self {
+            kind: Some(diff.kind()),
+            regions: diff.regions(0),
+            path: diff.path,
+            ours_hunks: diff.ours_hunks,
+            theirs_hunks: diff.theirs_hunks,
+            error: None,
+        }
+    }
+}
+
+/// How a file diverged from other worktrees (MCP `explain_conflict`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExplainOutput {
+    pub schema_version: u32,
+    pub file: String,
+    pub current_worktree: String,
+    pub current_branch: String,
+    pub explanations: Vec<Explanation>,
+}
+
+/// The file compared against one other worktree
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Explanation {
+    pub worktree: String,
+    pub branch: String,
+    /// Unset if the worktree couldn't be compared
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_merge_conflict: Option<bool>,
+    /// Merge base commit id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_commit: Option<String>,
+    #[serde(flatten)]
+    pub divergence: FileDivergence,
+}
+
+/// A failed request (HTTP API errors)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorOutput {
+    pub schema_version: u32,
+    pub error: String,
+}
+
+impl ErrorOutput {
+    pub fn new(error: impl Into<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            error: error.into(),
+        }
+    }
+}
+
+// ============================================================================
+// Daemon
+// ============================================================================
+
+/// One reply on the daemon socket
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DaemonReply {
+    pub schema_version: u32,
+    /// The result: `{"generation": n}` for `ping`, the unsuppressed pair
+    /// results for `pairs`, a check document for `check`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ok: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DaemonReply {
+    pub fn new(result: Result<serde_json::Value, String>) -> Self {
+        let (ok, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            ok,
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_carry_schema_version() {
+        let output = PatchCheckOutput::new(Vec::new());
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+
+        let schema = serde_json::to_value(schemars::schema_for!(StatusOutput)).unwrap();
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&"schema_version".into()));
+        // Nested library types are described too
+        assert!(schema["$defs"]["WorktreeStatus"].is_object());
+    }
+}
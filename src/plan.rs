@@ -0,0 +1,113 @@
+use clash_sh::WorktreeManager;
+use clash_sh::intent::{self, PlanOverlap};
+use clash_sh::output::PlansOutput;
+use clash_sh::registry::{PlannedPath, Registry, validate_pattern};
+use colored::Colorize;
+
+/// Record paths the current worktree plans to edit.
+pub fn run_plan_add(worktrees: &WorktreeManager, paths: &[String]) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+
+    for path in paths {
+        validate_pattern(path).map_err(|e| format!("invalid path '{}': {}", path, e))?;
+    }
+
+    let plans = paths
+        .iter()
+        .map(|p| PlannedPath::new(&current.id, p))
+        .collect();
+    Registry::open(worktrees)
+        .add_plans(plans)
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Planned {} path{} for {}",
+        paths.len().to_string().bright_green().bold(),
+        if paths.len() == 1 { "" } else { "s" },
+        current.id.bright_blue()
+    );
+
+    // Show overlaps right away so the planner can react before starting work
+    let overlaps: Vec<PlanOverlap> = intent::find_overlaps(worktrees)
+        .into_iter()
+        .filter(|o| o.planned_by == current.id || o.other == current.id)
+        .collect();
+    if !overlaps.is_empty() {
+        println!();
+        display_overlaps(&overlaps);
+    }
+    Ok(())
+}
+
+/// Remove planned paths for the current worktree (all of them if none are given).
+pub fn run_plan_remove(worktrees: &WorktreeManager, paths: &[String]) -> Result<(), String> {
+    let current = worktrees
+        .current()
+        .ok_or("current directory is not inside any known worktree")?;
+
+    let removed = Registry::open(worktrees)
+        .remove_plans(&current.id, paths)
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Removed {} planned path{} for {}",
+        removed.to_string().bright_green().bold(),
+        if removed == 1 { "" } else { "s" },
+        current.id.bright_blue()
+    );
+    Ok(())
+}
+
+/// List all planned paths and their overlaps.
+pub fn run_plan_list(worktrees: &WorktreeManager, json: bool) -> Result<(), String> {
+    let plans = Registry::open(worktrees)
+        .plans()
+        .map_err(|e| e.to_string())?;
+    let overlaps = intent::find_overlaps(worktrees);
+
+    if json {
+        let output = PlansOutput::new(plans, overlaps);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+
+    if plans.is_empty() {
+        println!("No planned paths");
+        return Ok(());
+    }
+
+    println!("{}", "Planned paths:".bright_cyan().bold());
+    for plan in &plans {
+        println!("  {}: {}", plan.worktree.bright_blue(), plan.path.yellow());
+    }
+
+    println!();
+    if overlaps.is_empty() {
+        println!("{} {}", "✓".bright_green().bold(), "No overlaps".green());
+    } else {
+        display_overlaps(&overlaps);
+    }
+    Ok(())
+}
+
+/// Print plan overlaps in the same style as `status`'s detailed view.
+pub fn display_overlaps(overlaps: &[PlanOverlap]) {
+    println!("{}", "Planned overlaps:".bright_cyan().bold());
+    for o in overlaps {
+        println!(
+            "  {} {} {}: {} {} {} ({})",
+            o.planned_by.bright_magenta(),
+            "vs".white(),
+            o.other.bright_magenta(),
+            o.path.yellow(),
+            "↔".bright_red(),
+            o.other_path.yellow(),
+            o.kind.to_string().bright_black()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Shared clash state stored in the common git directory
+//!
+//! All worktrees of a repository share one common `.git` directory, so state
+//! written there is visible to every agent regardless of which worktree it
+//! runs in. Clash keeps its files in a `clash/` subdirectory so they never
+//! mix with git's own data.
+//!
+//! Each kind of record lives in its own submodule, which extends
+//! [`Registry`] with methods for that record type.
+
+mod acks;
+mod claims;
+mod error;
+mod ledger;
+mod messages;
+mod plans;
+
+pub use acks::Ack;
+pub use claims::Claim;
+pub use error::{RegistryError, Result as RegistryResult};
+pub use ledger::{EditPhase, LedgerEntry};
+pub use messages::{Message, Recipient};
+pub use plans::PlannedPath;
+
+use crate::WorktreeManager;
+use error::Result;
+use globset::GlobBuilder;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Name of the clash directory inside the common git directory
+const REGISTRY_DIR: &str = "clash";
+
+/// How long to wait for another process to release a registry file
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Lock files older than this are assumed to belong to a crashed process
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+
+/// Handle to the shared clash directory of a repository
+#[derive(Debug, Clone)]
+pub struct Registry {
+    dir: PathBuf,
+}
+
+impl Registry {
+    /// Open the registry for the repository the worktrees belong to.
+    ///
+    /// This does not touch the filesystem; the directory is created
+    /// on the first write.
+    pub fn open(worktrees: &WorktreeManager) -> Self {
+        Self::for_common_dir(worktrees.common_dir())
+    }
+
+    /// Open the registry inside a known common git directory, without
+    /// discovering worktrees first
+    pub fn for_common_dir(common_dir: &Path) -> Self {
+        Self {
+            dir: common_dir.join(REGISTRY_DIR),
+        }
+    }
+
+    /// Get the registry directory path
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read a JSON file from the registry, returning the default if it doesn't exist
+    pub(crate) fn read_json<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T> {
+        let path = self.dir.join(name);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+            Err(source) => return Err(RegistryError::Io { path, source }),
+        };
+        serde_json::from_slice(&data).map_err(|source| RegistryError::Json { path, source })
+    }
+
+    /// Read-modify-write a JSON file while holding its lock.
+    ///
+    /// Several agents may update the same file at once, so the whole
+    /// cycle runs under a lock file and the result is written atomically.
+    pub(crate) fn update_json<T, R>(&self, name: &str, f: impl FnOnce(&mut T) -> R) -> Result<R>
+    where
+        T: DeserializeOwned + Serialize + Default,
+    {
+        let _lock = self.lock(name)?;
+        let mut value: T = self.read_json(name)?;
+        let result = f(&mut value);
+        self.write_json(name, &value)?;
+        Ok(result)
+    }
+
+    /// Write a JSON file atomically (temp file + rename)
+    fn write_json<T: Serialize>(&self, name: &str, value: &T) -> Result<()> {
+        let path = self.dir.join(name);
+        let tmp = self.dir.join(format!("{}.tmp", name));
+        let data = serde_json::to_vec_pretty(value).map_err(|source| RegistryError::Json {
+            path: path.clone(),
+            source,
+        })?;
+        fs::write(&tmp, data).map_err(|source| RegistryError::Io {
+            path: tmp.clone(),
+            source,
+        })?;
+        fs::rename(&tmp, &path).map_err(|source| RegistryError::Io { path, source })
+    }
+
+    /// Take the lock for a registry file, creating the registry directory if needed
+    fn lock(&self, name: &str) -> Result<LockGuard> {
+        fs::create_dir_all(&self.dir).map_err(|source| RegistryError::Io {
+            path: self.dir.clone(),
+            source,
+        })?;
+
+        let path = self.dir.join(format!("{}.lock", name));
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(LockGuard { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() > LOCK_TIMEOUT {
+                        return Err(RegistryError::Locked { path });
+                    }
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                Err(source) => return Err(RegistryError::Io { path, source }),
+            }
+        }
+    }
+}
+
+/// Build a glob for a repo-relative pattern; `*` does not cross directory separators
+fn build_glob(pattern: &str) -> std::result::Result<globset::Glob, globset::Error> {
+    GlobBuilder::new(pattern).literal_separator(true).build()
+}
+
+/// Check whether a repo-relative path matches a glob pattern.
+///
+/// Invalid patterns never match.
+pub(crate) fn glob_matches(pattern: &str, path: &str) -> bool {
+    build_glob(pattern).is_ok_and(|g| g.compile_matcher().is_match(path))
+}
+
+/// Validate a glob pattern, returning the parse error message if invalid
+pub fn validate_pattern(pattern: &str) -> std::result::Result<(), String> {
+    build_glob(pattern).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Removes the lock file when dropped
+struct LockGuard {
+    path: PathBuf,
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Check whether a lock file was left behind by a crashed process
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_LOCK_AGE)
+}
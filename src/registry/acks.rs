@@ -0,0 +1,78 @@
+//! Acknowledged conflicts
+//!
+//! An ack records that a conflict on one path between two worktrees is
+//! known and accepted. It stays valid only while both sides still have the
+//! blob they had when the ack was made.
+
+use super::Registry;
+use super::error::Result;
+use crate::Worktree;
+use serde::{Deserialize, Serialize};
+
+/// Registry file holding all acks
+const ACKS_FILE: &str = "acks.json";
+
+/// An accepted conflict on one path between two worktrees
+///
+/// Worktree ids are stored in sorted order so the same pair always maps
+/// to the same ack regardless of which side it was created from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ack {
+    pub wt1: String,
+    pub wt2: String,
+    pub path: String,
+    /// Blob id of the path at wt1's HEAD when acked (`None` if absent)
+    pub wt1_blob: Option<String>,
+    /// Blob id of the path at wt2's HEAD when acked (`None` if absent)
+    pub wt2_blob: Option<String>,
+    /// RFC 3339 timestamp of when the ack was created
+    pub created_at: String,
+}
+
+impl Ack {
+    /// Create an ack for the current state of `path` in both worktrees
+    pub fn new(a: &Worktree, b: &Worktree, path: &str) -> Self {
+        let (wt1, wt2) = if a.id <= b.id { (a, b) } else { (b, a) };
+        Self {
+            wt1: wt1.id.clone(),
+            wt2: wt2.id.clone(),
+            path: path.to_string(),
+            wt1_blob: wt1.head_blob_id(path),
+            wt2_blob: wt2.head_blob_id(path),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Check whether this ack covers the same pair and path
+    pub fn matches(&self, a: &str, b: &str, path: &str) -> bool {
+        let (wt1, wt2) = if a <= b { (a, b) } else { (b, a) };
+        self.wt1 == wt1 && self.wt2 == wt2 && self.path == path
+    }
+
+    /// Check whether this ack still applies to the given worktrees.
+    ///
+    /// An ack expires as soon as either side's blob for the path changes.
+    pub fn is_current(&self, a: &Worktree, b: &Worktree) -> bool {
+        if !self.matches(&a.id, &b.id, &self.path) {
+            return false;
+        }
+        let (wt1, wt2) = if a.id <= b.id { (a, b) } else { (b, a) };
+        wt1.head_blob_id(&self.path) == self.wt1_blob
+            && wt2.head_blob_id(&self.path) == self.wt2_blob
+    }
+}
+
+impl Registry {
+    /// Load all recorded acks
+    pub fn acks(&self) -> Result<Vec<Ack>> {
+        self.read_json(ACKS_FILE)
+    }
+
+    /// Record an ack, replacing any earlier ack for the same pair and path
+    pub fn add_ack(&self, ack: Ack) -> Result<()> {
+        self.update_json(ACKS_FILE, |acks: &mut Vec<Ack>| {
+            acks.retain(|a| !a.matches(&ack.wt1, &ack.wt2, &ack.path));
+            acks.push(ack);
+        })
+    }
+}
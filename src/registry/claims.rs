@@ -0,0 +1,176 @@
+//! Advisory file claims
+//!
+//! A claim says "this worktree (optionally, this session) owns these paths
+//! right now". Claims are advisory: nothing stops a write, but the check hook
+//! refuses edits to paths claimed by someone else.
+
+use super::error::Result;
+use super::{Registry, glob_matches};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Registry file holding all claims
+const CLAIMS_FILE: &str = "claims.json";
+
+/// A glob pattern claimed by a worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Claim {
+    /// Id of the worktree holding the claim
+    pub worktree: String,
+    /// Agent session holding the claim, if it was made on behalf of one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Repo-relative glob pattern (e.g. `src/auth/**`)
+    pub pattern: String,
+    /// RFC 3339 timestamp of when the claim was made
+    pub created_at: String,
+}
+
+impl Claim {
+    /// Create a claim for a pattern held by a worktree
+    pub fn new(worktree: &str, session: Option<&str>, pattern: &str) -> Self {
+        Self {
+            worktree: worktree.to_string(),
+            session: session.map(str::to_string),
+            pattern: pattern.trim_start_matches("./").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Check whether a repo-relative path falls under this claim.
+    ///
+    /// Invalid patterns never match.
+    pub fn matches(&self, path: &str) -> bool {
+        glob_matches(&self.pattern, path)
+    }
+
+    /// Check whether this claim and `pattern` could cover the same file:
+    /// either glob matches the other pattern (`src/**` and `src/api/*.rs`)
+    pub fn overlaps(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim_start_matches("./");
+        self.pattern == pattern
+            || glob_matches(&self.pattern, pattern)
+            || glob_matches(pattern, &self.pattern)
+    }
+
+    /// Check whether this claim belongs to the given worktree and session.
+    ///
+    /// A claim without a session belongs to every session in its worktree,
+    /// and a caller without a session owns every claim in its worktree.
+    pub fn is_held_by(&self, worktree: &str, session: Option<&str>) -> bool {
+        self.worktree == worktree
+            && match (self.session.as_deref(), session) {
+                (Some(held), Some(caller)) => held == caller,
+                _ => true,
+            }
+    }
+}
+
+impl Registry {
+    /// Load all recorded claims
+    pub fn claims(&self) -> Result<Vec<Claim>> {
+        self.read_json(CLAIMS_FILE)
+    }
+
+    /// Record claims, skipping patterns the same holder already claimed
+    pub fn add_claims(&self, new: Vec<Claim>) -> Result<()> {
+        self.update_json(CLAIMS_FILE, |claims: &mut Vec<Claim>| {
+            for claim in new {
+                let exists = claims.iter().any(|c| {
+                    c.worktree == claim.worktree
+                        && c.session == claim.session
+                        && c.pattern == claim.pattern
+                });
+                if !exists {
+                    claims.push(claim);
+                }
+            }
+        })
+    }
+
+    /// Release claims held by a worktree (and session, if given).
+    ///
+    /// With no patterns, every claim of the holder is released.
+    /// Returns the number of claims removed.
+    pub fn release_claims(
+        &self,
+        worktree: &str,
+        session: Option<&str>,
+        patterns: &[String],
+    ) -> Result<usize> {
+        self.update_json(CLAIMS_FILE, |claims: &mut Vec<Claim>| {
+            let before = claims.len();
+            claims.retain(|c| {
+                let held = c.worktree == worktree
+                    && (session.is_none() || c.session.as_deref() == session);
+                let selected = patterns.is_empty()
+                    || patterns
+                        .iter()
+                        .any(|p| p.trim_start_matches("./") == c.pattern);
+                !(held && selected)
+            });
+            before - claims.len()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_can_be_released_and_claimed_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::for_common_dir(dir.path());
+        registry
+            .add_claims(vec![
+                Claim::new("a", Some("s1"), "./src/**"),
+                Claim::new("a", Some("s1"), "src/**"),
+                Claim::new("a", None, "docs/*.md"),
+                Claim::new("b", None, "src/**"),
+            ])
+            .unwrap();
+        assert_eq!(registry.claims().unwrap().len(), 3);
+
+        let released = registry.release_claims("a", None, &["./src/**".to_string()]);
+        assert_eq!(released.unwrap(), 1);
+        let held: Vec<_> = registry
+            .claims()
+            .unwrap()
+            .into_iter()
+            .map(|c| c.pattern)
+            .collect();
+        assert_eq!(held, vec!["docs/*.md", "src/**"]);
+
+        registry
+            .add_claims(vec![Claim::new("a", Some("s2"), "src/**")])
+            .unwrap();
+        assert_eq!(registry.release_claims("a", None, &[]).unwrap(), 2);
+        assert_eq!(registry.claims().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn session_claims_are_held_only_by_their_session() {
+        let session = Claim::new("a", Some("s1"), "src/**");
+        assert!(session.is_held_by("a", Some("s1")));
+        assert!(!session.is_held_by("a", Some("s2")));
+        assert!(session.is_held_by("a", None));
+        assert!(!session.is_held_by("b", Some("s1")));
+
+        let worktree = Claim::new("a", None, "src/**");
+        assert!(worktree.is_held_by("a", Some("s2")));
+        assert!(!worktree.is_held_by("b", None));
+    }
+
+    #[test]
+    fn overlapping_patterns_are_contested() {
+        let claim = Claim::new("a", None, "src/api/*.rs");
+        assert!(claim.overlaps("src/**"));
+        assert!(claim.overlaps("./src/api/*.rs"));
+        assert!(claim.overlaps("src/api/handlers.rs"));
+        assert!(Claim::new("a", None, "src/**").overlaps("src/api/*.rs"));
+        assert!(!claim.overlaps("src/db/**"));
+        assert!(!claim.overlaps("docs/*.md"));
+    }
+}
@@ -0,0 +1,29 @@
+//! Error types for registry operations
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Errors that can occur while reading or writing the shared registry
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    /// Reading or writing a registry file failed
+    #[error("Failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// A registry file contains invalid JSON
+    #[error("Invalid registry file {path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    /// Another process held the lock for too long
+    #[error("Timed out waiting for lock {path}")]
+    Locked { path: PathBuf },
+}
+
+/// Result type alias using RegistryError
+pub type Result<T> = std::result::Result<T, RegistryError>;
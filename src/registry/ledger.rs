@@ -0,0 +1,155 @@
+//! Edit activity ledger
+//!
+//! Every edit an agent attempts (pre-edit hook) or completes (post-edit
+//! hook) is appended to a shared log, one JSON object per line. It answers
+//! "who touched this file in the last few minutes" without waiting for the
+//! change to show up in a worktree's diff.
+//!
+//! The ledger is append-only; once it grows past a size limit, entries older
+//! than a day are dropped on the next write.
+
+use super::Registry;
+use super::error::{RegistryError, Result};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::Duration;
+
+/// Registry file holding the ledger, one entry per line
+const LEDGER_FILE: &str = "ledger.jsonl";
+
+/// Size past which old entries are dropped on the next write
+const MAX_LEDGER_BYTES: u64 = 1024 * 1024;
+
+/// How long entries survive once the ledger is compacted
+const LEDGER_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Whether an edit was about to happen or has happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EditPhase {
+    /// Reported by a pre-edit hook, before the tool ran
+    Attempted,
+    /// Reported by a post-edit hook, after the tool ran
+    Completed,
+}
+
+/// One edit of one file by an agent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LedgerEntry {
+    /// Agent session making the edit, if the hook reported one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Id of the worktree the file belongs to
+    pub worktree: String,
+    /// Repo-relative path of the file
+    pub path: String,
+    /// RFC 3339 timestamp of when the edit was reported
+    pub timestamp: String,
+    /// Tool that made the edit (e.g. `Edit`, `Bash`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    pub phase: EditPhase,
+    /// Decision the check hook made (`allow`, `ask` or `deny`), for attempts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<String>,
+}
+
+impl LedgerEntry {
+    /// Create an entry for an edit reported now
+    pub fn new(worktree: &str, path: &str, phase: EditPhase) -> Self {
+        Self {
+            session: None,
+            worktree: worktree.to_string(),
+            path: path.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            tool: None,
+            phase,
+            decision: None,
+        }
+    }
+
+    /// Time since the edit was reported (`None` if the timestamp is invalid)
+    pub fn age(&self) -> Option<Duration> {
+        let at = DateTime::parse_from_rfc3339(&self.timestamp).ok()?;
+        (Utc::now() - at.with_timezone(&Utc)).to_std().ok()
+    }
+
+    /// Whether the edit was reported within `window` of now
+    pub fn is_within(&self, window: Duration) -> bool {
+        self.age().is_some_and(|age| age <= window)
+    }
+}
+
+impl Registry {
+    /// Load every ledger entry, oldest first.
+    ///
+    /// Lines that don't parse (e.g. a write cut short) are skipped.
+    pub fn ledger(&self) -> Result<Vec<LedgerEntry>> {
+        let path = self.dir.join(LEDGER_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(RegistryError::Io { path, source }),
+        };
+        Ok(text
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Entries reported within `window` of now, optionally for one path only
+    pub fn recent_edits(&self, path: Option<&str>, window: Duration) -> Result<Vec<LedgerEntry>> {
+        Ok(self
+            .ledger()?
+            .into_iter()
+            .filter(|e| path.is_none_or(|p| e.path == p) && e.is_within(window))
+            .collect())
+    }
+
+    /// Append entries to the ledger, compacting it first if it grew too large
+    pub fn record_edits(&self, entries: &[LedgerEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let _lock = self.lock(LEDGER_FILE)?;
+        let path = self.dir.join(LEDGER_FILE);
+
+        if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LEDGER_BYTES) {
+            let kept: Vec<LedgerEntry> = self
+                .ledger()?
+                .into_iter()
+                .filter(|e| e.is_within(LEDGER_RETENTION))
+                .collect();
+            // Readers don't take the lock, so replace the file atomically
+            let tmp = format!("{}.tmp", LEDGER_FILE);
+            self.write_lines(&tmp, &kept, false)?;
+            fs::rename(self.dir.join(&tmp), &path)
+                .map_err(|source| RegistryError::Io { path, source })?;
+        }
+        self.write_lines(LEDGER_FILE, entries, true)
+    }
+
+    /// Write entries as JSON lines to a registry file, appending or replacing
+    fn write_lines(&self, name: &str, entries: &[LedgerEntry], append: bool) -> Result<()> {
+        let path = self.dir.join(name);
+        let mut data = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut data, entry).map_err(|source| RegistryError::Json {
+                path: path.clone(),
+                source,
+            })?;
+            data.push(b'\n');
+        }
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&data))
+            .map_err(|source| RegistryError::Io { path, source })
+    }
+}
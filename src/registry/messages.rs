@@ -0,0 +1,134 @@
+//! Message board between agents
+//!
+//! A message is a short note one worktree leaves for the others ("I'm
+//! changing the `User` struct, hold off"). It is addressed to everyone, to
+//! one worktree, or to whoever edits paths matching a glob, and stays unread
+//! for each worktree until that worktree acknowledges it.
+
+use super::error::Result;
+use super::{Registry, glob_matches};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Registry file holding all messages
+const MESSAGES_FILE: &str = "messages.json";
+
+/// Messages older than this are dropped when a new one is posted
+const MESSAGE_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Who a message is for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", content = "target", rename_all = "lowercase")]
+pub enum Recipient {
+    /// Every other worktree
+    All,
+    /// One worktree, by id
+    Worktree(String),
+    /// Any worktree editing a path matching this glob
+    Path(String),
+}
+
+/// A note left by one worktree for others
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Message {
+    /// Sequential id, unique within the repository
+    pub id: u64,
+    /// Id of the worktree that sent the message
+    pub from: String,
+    /// Agent session that sent the message, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    pub to: Recipient,
+    pub body: String,
+    /// RFC 3339 timestamp of when the message was sent
+    pub created_at: String,
+    /// Ids of the worktrees that have acknowledged the message
+    #[serde(default)]
+    pub acked_by: Vec<String>,
+}
+
+impl Message {
+    /// Create a message from a worktree; the id is assigned when it is posted
+    pub fn new(from: &str, session: Option<&str>, to: Recipient, body: &str) -> Self {
+        Self {
+            id: 0,
+            from: from.to_string(),
+            session: session.map(str::to_string),
+            to: match to {
+                Recipient::Path(p) => Recipient::Path(p.trim_start_matches("./").to_string()),
+                to => to,
+            },
+            body: body.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            acked_by: Vec::new(),
+        }
+    }
+
+    /// Check whether the message reaches a worktree (by id) at all.
+    ///
+    /// Path-scoped messages reach every worktree, since any of them may
+    /// edit a matching file. A worktree never receives its own messages.
+    pub fn is_for(&self, worktree: &str) -> bool {
+        self.from != worktree
+            && match &self.to {
+                Recipient::All | Recipient::Path(_) => true,
+                Recipient::Worktree(id) => id == worktree,
+            }
+    }
+
+    /// Check whether the message concerns a worktree editing `path`
+    pub fn concerns(&self, worktree: &str, path: &str) -> bool {
+        self.is_for(worktree)
+            && match &self.to {
+                Recipient::Path(pattern) => pattern == path || glob_matches(pattern, path),
+                _ => true,
+            }
+    }
+
+    /// Check whether a worktree (by id) has acknowledged the message
+    pub fn is_acked_by(&self, worktree: &str) -> bool {
+        self.acked_by.iter().any(|w| w == worktree)
+    }
+}
+
+impl Registry {
+    /// Load all messages, oldest first
+    pub fn messages(&self) -> Result<Vec<Message>> {
+        self.read_json(MESSAGES_FILE)
+    }
+
+    /// Post a message, returning its id.
+    ///
+    /// Messages past the retention period are dropped at the same time.
+    pub fn post_message(&self, mut message: Message) -> Result<u64> {
+        self.update_json(MESSAGES_FILE, |messages: &mut Vec<Message>| {
+            message.id = messages.iter().map(|m| m.id).max().unwrap_or(0) + 1;
+            let id = message.id;
+            messages.retain(|m| {
+                chrono::DateTime::parse_from_rfc3339(&m.created_at)
+                    .ok()
+                    .and_then(|at| (chrono::Utc::now() - at.to_utc()).to_std().ok())
+                    .is_none_or(|age| age <= MESSAGE_RETENTION)
+            });
+            messages.push(message);
+            id
+        })
+    }
+
+    /// Mark messages as acknowledged by a worktree.
+    ///
+    /// Returns the number of messages newly acknowledged.
+    pub fn ack_messages(&self, worktree: &str, ids: &[u64]) -> Result<usize> {
+        self.update_json(MESSAGES_FILE, |messages: &mut Vec<Message>| {
+            let mut acked = 0;
+            for message in messages.iter_mut() {
+                if ids.contains(&message.id) && !message.is_acked_by(worktree) {
+                    message.acked_by.push(worktree.to_string());
+                    acked += 1;
+                }
+            }
+            acked
+        })
+    }
+}
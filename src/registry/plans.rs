@@ -0,0 +1,80 @@
+//! Planned paths (edit intents)
+//!
+//! Before an agent starts a task, its planner records the paths it expects
+//! to touch. Overlapping plans can then be spotted before any code is written.
+
+use super::error::Result;
+use super::{Registry, glob_matches};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Registry file holding all planned paths
+const PLANS_FILE: &str = "plans.json";
+
+/// A path (or glob pattern) a worktree plans to edit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PlannedPath {
+    /// Id of the worktree that plans the edit
+    pub worktree: String,
+    /// Repo-relative path or glob pattern
+    pub path: String,
+    /// RFC 3339 timestamp of when the path was planned
+    pub created_at: String,
+}
+
+impl PlannedPath {
+    /// Create a planned path for a worktree
+    pub fn new(worktree: &str, path: &str) -> Self {
+        Self {
+            worktree: worktree.to_string(),
+            path: path.trim_start_matches("./").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Check whether a concrete repo-relative file falls under this plan
+    pub fn covers(&self, file: &str) -> bool {
+        self.path == file || glob_matches(&self.path, file)
+    }
+
+    /// Check whether two plans could touch the same file
+    pub fn overlaps(&self, other: &PlannedPath) -> bool {
+        self.covers(&other.path) || other.covers(&self.path)
+    }
+}
+
+impl Registry {
+    /// Load all planned paths
+    pub fn plans(&self) -> Result<Vec<PlannedPath>> {
+        self.read_json(PLANS_FILE)
+    }
+
+    /// Record planned paths, skipping ones the worktree already planned
+    pub fn add_plans(&self, new: Vec<PlannedPath>) -> Result<()> {
+        self.update_json(PLANS_FILE, |plans: &mut Vec<PlannedPath>| {
+            for plan in new {
+                if !plans
+                    .iter()
+                    .any(|p| p.worktree == plan.worktree && p.path == plan.path)
+                {
+                    plans.push(plan);
+                }
+            }
+        })
+    }
+
+    /// Remove planned paths for a worktree (all of them if `paths` is empty).
+    ///
+    /// Returns the number of planned paths removed.
+    pub fn remove_plans(&self, worktree: &str, paths: &[String]) -> Result<usize> {
+        self.update_json(PLANS_FILE, |plans: &mut Vec<PlannedPath>| {
+            let before = plans.len();
+            plans.retain(|p| {
+                let selected =
+                    paths.is_empty() || paths.iter().any(|x| x.trim_start_matches("./") == p.path);
+                !(p.worktree == worktree && selected)
+            });
+            before - plans.len()
+        })
+    }
+}
@@ -0,0 +1,474 @@
+//! `clash report`: standalone conflict reports for PR comments and artifacts
+//!
+//! `clash status` is meant for a terminal and its colors turn into escape
+//! codes when pasted elsewhere. The report carries the same matrix plus the
+//! worktrees' metadata, every pair's conflicting files with how they
+//! diverged, and the colliding hunks, as Markdown or a self-contained HTML
+//! page (with the hunks in collapsible sections).
+
+use crate::format::xml_escape;
+use crate::status::{StatusDisplay, check_pairs};
+use clash_sh::{ConflictRegion, Hunk, PairFileDiff, Side, WorktreeManager, WorktreePairConflict};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Format of `clash report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/// A conflicting file of a pair and how the two sides changed it
+struct FileReport {
+    path: String,
+    /// `None` if the file couldn't be diffed
+    diff: Option<PairFileDiff>,
+}
+
+impl FileReport {
+    fn kind(&self) -> String {
+        self.diff
+            .as_ref()
+            .map_or_else(|| "unknown".to_string(), |d| d.kind().to_string())
+    }
+
+    /// Headed diff sections: one per colliding region, or both sides' whole
+    /// changes when they touch different lines (git may still merge those)
+    fn sections(&self, pair: &WorktreePairConflict) -> Vec<(String, String)> {
+        let Some(diff) = &self.diff else {
+            return Vec::new();
+        };
+        let regions = diff.regions(0);
+        if regions.is_empty() {
+            if diff.ours_hunks.is_empty() && diff.theirs_hunks.is_empty() {
+                return Vec::new();
+            }
+            let heading = "Both sides changed the file, in different places".to_string();
+            return vec![(
+                heading,
+                sides_text(pair, &diff.ours_hunks, &diff.theirs_hunks),
+            )];
+        }
+        regions
+            .iter()
+            .map(|region| {
+                let text = sides_text(
+                    pair,
+                    std::slice::from_ref(&region.ours),
+                    std::slice::from_ref(&region.theirs),
+                );
+                (base_lines(region), text)
+            })
+            .collect()
+    }
+}
+
+/// Everything a report shows, gathered once for both formats
+struct Report<'a> {
+    worktrees: &'a WorktreeManager,
+    pairs: Vec<WorktreePairConflict>,
+    matrix: Vec<Vec<Option<Vec<String>>>>,
+    /// Conflicting files per pair, indexed like `pairs`
+    files: Vec<Vec<FileReport>>,
+    suppressed: usize,
+}
+
+/// Build a conflict report and print it, or write it to `output`.
+///
+/// Ignored and acknowledged conflicts are left out unless `all` is set.
+pub fn run_report(
+    worktrees: &WorktreeManager,
+    format: ReportFormat,
+    all: bool,
+    output: Option<&Path>,
+) -> Result<(), String> {
+    let report = Report::build(worktrees, all);
+    let text = match format {
+        ReportFormat::Markdown => report.markdown(),
+        ReportFormat::Html => report.html(),
+    };
+    match output {
+        Some(path) => std::fs::write(path, text)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+impl<'a> Report<'a> {
+    fn build(worktrees: &'a WorktreeManager, all: bool) -> Self {
+        let (pairs, suppressed) = check_pairs(worktrees, all);
+        let matrix = StatusDisplay::new(worktrees, all).build_conflict_matrix(&pairs);
+        let files = pairs
+            .iter()
+            .map(|pair| {
+                // Merge conflicts are computed on HEAD, so diff the commits too
+                let sides = worktrees
+                    .find(&pair.wt1.id)
+                    .ok()
+                    .zip(worktrees.find(&pair.wt2.id).ok());
+                pair.conflicting_files
+                    .iter()
+                    .map(|path| {
+                        let diff = sides.and_then(|(ours, theirs)| {
+                            ours.diff_file_against(theirs, path, Side::Head).ok()
+                        });
+                        FileReport {
+                            path: path.clone(),
+                            diff,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            worktrees,
+            pairs,
+            matrix,
+            files,
+            suppressed,
+        }
+    }
+
+    /// One-line summary of what was found
+    fn summary(&self) -> String {
+        let conflicting = self
+            .pairs
+            .iter()
+            .filter(|p| !p.conflicting_files.is_empty())
+            .count();
+        let files: usize = self.pairs.iter().map(|p| p.conflicting_files.len()).sum();
+        let mut summary = format!(
+            "{} worktrees, {} of {} pairs conflicting, {} conflicting file{}",
+            self.worktrees.len(),
+            conflicting,
+            self.pairs.len(),
+            files,
+            if files == 1 { "" } else { "s" }
+        );
+        let errors = self.pairs.iter().filter(|p| p.error.is_some()).count();
+        if errors > 0 {
+            let _ = write!(summary, ", {} failed to check", errors);
+        }
+        if self.suppressed > 0 {
+            let _ = write!(
+                summary,
+                ", {} suppressed by .clashignore or acks",
+                self.suppressed
+            );
+        }
+        summary
+    }
+
+    /// Text of a matrix cell: `-` on the diagonal, `OK`, a count, or `?`
+    fn cell(&self, i: usize, j: usize) -> String {
+        if i == j {
+            return "-".to_string();
+        }
+        match &self.matrix[i][j] {
+            Some(files) if files.is_empty() => "OK".to_string(),
+            Some(files) => files.len().to_string(),
+            None => "?".to_string(),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# Clash conflict report\n");
+        let _ = writeln!(md, "Generated {}: {}.\n", generated_at(), self.summary());
+
+        md.push_str("## Worktrees\n\n");
+        md.push_str("| Worktree | Branch | Status | HEAD | Path |\n");
+        md.push_str("|----------|--------|--------|------|------|\n");
+        for wt in self.worktrees.iter() {
+            let head = wt
+                .head_commit()
+                .map(|c| format!("`{}` {}", short_id(&c.id), md_cell(&c.summary)))
+                .unwrap_or_default();
+            let _ = writeln!(
+                md,
+                "| {} | `{}` | {} | {} | `{}` |",
+                md_cell(&wt.id),
+                md_cell(&wt.branch),
+                wt.status,
+                head,
+                md_cell(&wt.path.display().to_string())
+            );
+        }
+
+        md.push_str("\n## Conflict matrix\n\n|  |");
+        for wt in self.worktrees.iter() {
+            let _ = write!(md, " {} |", md_cell(&wt.branch));
+        }
+        md.push_str("\n|---|");
+        md.push_str(&"---|".repeat(self.worktrees.len()));
+        md.push('\n');
+        for (i, wt) in self.worktrees.iter().enumerate() {
+            let _ = write!(md, "| **{}** |", md_cell(&wt.branch));
+            for j in 0..self.worktrees.len() {
+                let _ = write!(md, " {} |", self.cell(i, j));
+            }
+            md.push('\n');
+        }
+
+        md.push_str("\n## Conflicts\n");
+        for (pair, files) in self.pairs.iter().zip(&self.files) {
+            let _ = writeln!(md, "\n### {}\n", pair_title(pair));
+            if let Some(error) = &pair.error {
+                let _ = writeln!(md, "Could not check this pair: {}", error);
+                continue;
+            }
+            if files.is_empty() {
+                md.push_str("No conflicts.\n");
+                continue;
+            }
+            for file in files {
+                let _ = writeln!(md, "- `{}` ({})", file.path, file.kind());
+            }
+            for file in files {
+                let sections = file.sections(pair);
+                if sections.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(md, "\n#### `{}`", file.path);
+                for (heading, text) in sections {
+                    let fence = if text.contains("```") { "````" } else { "```" };
+                    let _ = writeln!(md, "\n{}:\n\n{}diff\n{}{}", heading, fence, text, fence);
+                }
+            }
+        }
+        md
+    }
+
+    fn html(&self) -> String {
+        let mut html = String::new();
+        html.push_str(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Clash conflict report</title>\n<style>\n",
+        );
+        html.push_str(HTML_STYLE);
+        html.push_str("</style>\n</head>\n<body>\n<h1>Clash conflict report</h1>\n");
+        let _ = writeln!(
+            html,
+            "<p>Generated {}: {}.</p>",
+            generated_at(),
+            xml_escape(&self.summary())
+        );
+
+        html.push_str("<h2>Worktrees</h2>\n<table>\n<tr><th>Worktree</th><th>Branch</th><th>Status</th><th>HEAD</th><th>Path</th></tr>\n");
+        for wt in self.worktrees.iter() {
+            let head = wt
+                .head_commit()
+                .map(|c| {
+                    format!(
+                        "<code>{}</code> {}",
+                        short_id(&c.id),
+                        xml_escape(&c.summary)
+                    )
+                })
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td><code>{}</code></td><td class=\"status-{}\">{}</td><td>{}</td><td><code>{}</code></td></tr>",
+                xml_escape(&wt.id),
+                xml_escape(&wt.branch),
+                wt.status,
+                wt.status,
+                head,
+                xml_escape(&wt.path.display().to_string())
+            );
+        }
+
+        html.push_str(
+            "</table>\n<h2>Conflict matrix</h2>\n<table class=\"matrix\">\n<tr><th></th>",
+        );
+        for wt in self.worktrees.iter() {
+            let _ = write!(html, "<th>{}</th>", xml_escape(&wt.branch));
+        }
+        html.push_str("</tr>\n");
+        for (i, wt) in self.worktrees.iter().enumerate() {
+            let _ = write!(html, "<tr><th>{}</th>", xml_escape(&wt.branch));
+            for j in 0..self.worktrees.len() {
+                let cell = self.cell(i, j);
+                let class = match cell.as_str() {
+                    "-" => "self",
+                    "OK" => "ok",
+                    "?" => "unknown",
+                    _ => "conflict",
+                };
+                let _ = write!(html, "<td class=\"{}\">{}</td>", class, cell);
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n<h2>Conflicts</h2>\n");
+
+        for (pair, files) in self.pairs.iter().zip(&self.files) {
+            let _ = writeln!(html, "<h3>{}</h3>", xml_escape(&pair_title(pair)));
+            if let Some(error) = &pair.error {
+                let _ = writeln!(
+                    html,
+                    "<p>Could not check this pair: {}</p>",
+                    xml_escape(error)
+                );
+                continue;
+            }
+            if files.is_empty() {
+                html.push_str("<p>No conflicts.</p>\n");
+                continue;
+            }
+            for file in files {
+                let _ = writeln!(
+                    html,
+                    "<details>\n<summary><code>{}</code> ({})</summary>",
+                    xml_escape(&file.path),
+                    file.kind()
+                );
+                for (heading, text) in file.sections(pair) {
+                    let _ = writeln!(html, "<p>{}:</p>\n<pre>", heading);
+                    for line in text.lines() {
+                        let class = match line.chars().next() {
+                            Some('+') => "add",
+                            Some('-') => "del",
+                            Some('@') => "hunk",
+                            _ => "side",
+                        };
+                        let _ = writeln!(
+                            html,
+                            "<span class=\"{}\">{}</span>",
+                            class,
+                            xml_escape(line)
+                        );
+                    }
+                    html.push_str("</pre>\n");
+                }
+                html.push_str("</details>\n");
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Styles for the HTML report, kept inline so it stands alone
+const HTML_STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em; color: #1f2328; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #d0d7de; padding: 4px 10px; text-align: left; }
+.matrix td { text-align: center; }
+td.ok { color: #1a7f37; }
+td.conflict { color: #cf222e; font-weight: bold; }
+td.self, td.unknown { color: #8c959f; }
+td.status-dirty, td.status-conflicted { color: #9a6700; }
+details { margin: 0.5em 0; }
+summary { cursor: pointer; }
+pre { background: #f6f8fa; padding: 8px; overflow-x: auto; }
+pre span { display: block; }
+.add { color: #1a7f37; }
+.del { color: #cf222e; }
+.hunk { color: #0969da; }
+.side { color: #57606a; font-weight: bold; }
+";
+
+/// Both sides' hunks as diff text, each under its worktree
+fn sides_text(pair: &WorktreePairConflict, ours: &[Hunk], theirs: &[Hunk]) -> String {
+    let mut text = String::new();
+    for (wt, hunks) in [(&pair.wt1, ours), (&pair.wt2, theirs)] {
+        let _ = writeln!(text, "# {} [{}]", wt.id, wt.branch);
+        for hunk in hunks {
+            write_hunk(&mut text, hunk);
+        }
+    }
+    text
+}
+
+/// Append a hunk in unified diff style
+fn write_hunk(out: &mut String, hunk: &Hunk) {
+    let _ = writeln!(
+        out,
+        "@@ -{},{} +{},{} @@",
+        hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+    );
+    for line in &hunk.removed {
+        let _ = writeln!(out, "-{}", line);
+    }
+    for line in &hunk.added {
+        let _ = writeln!(out, "+{}", line);
+    }
+}
+
+/// e.g. `Merge-base lines 4-7`
+fn base_lines(region: &ConflictRegion) -> String {
+    format!(
+        "Merge-base lines {}-{}",
+        region.base_start,
+        region.base_start + region.base_lines.max(1) - 1
+    )
+}
+
+/// e.g. `main [main] vs feature-a [feat-a]`
+fn pair_title(pair: &WorktreePairConflict) -> String {
+    format!(
+        "{} [{}] vs {} [{}]",
+        pair.wt1.id, pair.wt1.branch, pair.wt2.id, pair.wt2.branch
+    )
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..7).unwrap_or(id)
+}
+
+fn generated_at() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// Make text safe inside a Markdown table cell
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    #[test]
+    fn md_cell_escapes_pipes_and_newlines() {
+        assert_eq!(md_cell("a|b\nc"), "a\\|b c");
+    }
+
+    #[test]
+    fn names_are_escaped_in_both_formats() {
+        let repo = TestRepo::new(&[("x<y>|&.txt", "base\n")]);
+        repo.add_worktree("b<i>|&");
+        repo.write("b<i>|&", "x<y>|&.txt", "theirs\n");
+        repo.commit("b<i>|&", "theirs");
+        repo.write("main", "x<y>|&.txt", "ours\n");
+        repo.commit("main", "ours");
+
+        let worktrees = repo.manager();
+        let report = Report::build(&worktrees, true);
+        assert_eq!(report.files[0].len(), 1);
+
+        let md = report.markdown();
+        assert!(md.contains("| `b<i>\\|&` |"), "{}", md);
+        assert!(md.contains("| **b<i>\\|&** |"), "{}", md);
+        assert!(!md.contains("b<i>|&` |"), "{}", md);
+
+        let html = report.html();
+        assert!(!html.contains("<i>"), "{}", html);
+        assert!(html.contains("<code>b&lt;i&gt;|&amp;</code>"), "{}", html);
+        assert!(
+            html.contains("<code>x&lt;y&gt;|&amp;.txt</code>"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("vs b&lt;i&gt;|&amp; [b&lt;i&gt;|&amp;]"),
+            "{}",
+            html
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! `clash schema`: JSON Schemas of the documents clash produces
+//!
+//! The documents themselves live in [`clash_sh::output`]; each carries a
+//! `schema_version` so consumers can tell which schema to validate against.
+
+use clash_sh::output::{
+    BatchesOutput, ChangeEvent, CheckOutput, DaemonReply, ErrorOutput, ExplainOutput, HookOutput,
+    LedgerOutput, MatrixOutput, MessagesOutput, PairOutput, PatchCheckOutput, PlansOutput,
+    StatusOutput, WorktreeDetail, WorktreesOutput,
+};
+use schemars::schema_for;
+
+/// JSON document whose schema `clash schema` prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaDocument {
+    /// `clash status --json`, `/api/status`, MCP `status`
+    Status,
+    /// `clash check <file>`, `/api/check`, MCP `check_file`
+    Check,
+    /// `clash check --patch <file>`
+    CheckPatch,
+    /// `clash check --hook generic`
+    Hook,
+    /// `clash ledger --json`
+    Ledger,
+    /// `clash msg list --json`
+    Msg,
+    /// `clash plan list --json`
+    Plan,
+    /// `clash batches --json`
+    Batches,
+    /// `/api/worktrees`, MCP `list_worktrees`
+    Worktrees,
+    /// `/api/worktree`
+    Worktree,
+    /// `/api/matrix` and the `matrix` event of `/api/events`
+    Matrix,
+    /// The `change` event of `/api/events`
+    Change,
+    /// `/api/pair`
+    Pair,
+    /// MCP `explain_conflict`
+    Explain,
+    /// HTTP API errors
+    Error,
+    /// Replies on the daemon socket
+    Daemon,
+}
+
+/// Print the JSON Schema of a document
+pub fn run_schema(document: SchemaDocument) -> Result<(), String> {
+    let schema = match document {
+        SchemaDocument::Status => schema_for!(StatusOutput),
+        SchemaDocument::Check => schema_for!(CheckOutput),
+        SchemaDocument::CheckPatch => schema_for!(PatchCheckOutput),
+        SchemaDocument::Hook => schema_for!(HookOutput),
+        SchemaDocument::Ledger => schema_for!(LedgerOutput),
+        SchemaDocument::Msg => schema_for!(MessagesOutput),
+        SchemaDocument::Plan => schema_for!(PlansOutput),
+        SchemaDocument::Batches => schema_for!(BatchesOutput),
+        SchemaDocument::Worktrees => schema_for!(WorktreesOutput),
+        SchemaDocument::Worktree => schema_for!(WorktreeDetail),
+        SchemaDocument::Matrix => schema_for!(MatrixOutput),
+        SchemaDocument::Change => schema_for!(ChangeEvent),
+        SchemaDocument::Pair => schema_for!(PairOutput),
+        SchemaDocument::Explain => schema_for!(ExplainOutput),
+        SchemaDocument::Error => schema_for!(ErrorOutput),
+        SchemaDocument::Daemon => schema_for!(DaemonReply),
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
@@ -0,0 +1,487 @@
+//! Local HTTP/JSON API with a Server-Sent Events stream
+//!
+//! `clash serve` exposes worktrees, the pair matrix and per-file checks on
+//! localhost, answered from a [`LiveState`] kept current by the file
+//! watcher. Dashboards can subscribe to `/api/events` instead of polling.
+//!
+//! Endpoints (all `GET`, JSON unless noted; `all=true` includes ignored and
+//! acknowledged conflicts):
+//!
+//! - `/` - self-contained dashboard page (what `clash ui --web` opens)
+//! - `/api/worktrees` - all worktrees with branch and status
+//! - `/api/worktree?id=<id>` - one worktree's HEAD commit, uncommitted files,
+//!   claims and planned paths
+//! - `/api/matrix` - every worktree pair with its conflicting files
+//! - `/api/pair?wt1=<id>&wt2=<id>` - a pair's conflicting files with hunks
+//! - `/api/status` - same document as `clash status --json`
+//! - `/api/check?path=<file>&session=<id>` - same document as `clash check <file>`
+//! - `/api/events` - SSE stream: a `matrix` event on connect, then a
+//!   `change` event whenever a pair's conflicting files change
+//!
+//! Listening on 127.0.0.1 alone doesn't keep web pages out: a DNS rebinding
+//! attack points an attacker's host name at 127.0.0.1. So requests must name
+//! the server as `localhost` or `127.0.0.1` in `Host`, and API requests from
+//! a page on any other origin are refused.
+
+use crate::check;
+use crate::live::{LiveState, Snapshot};
+use crate::status::{apply_suppressions, build_status_output};
+use clash_sh::output::{
+    ChangeEvent, ConflictInfo, ErrorOutput, FileDivergence, MatrixOutput, PairChange, PairOutput,
+    SCHEMA_VERSION, WorktreeDetail, WorktreesOutput,
+};
+use clash_sh::registry::Registry;
+use clash_sh::{Side, Suppressions};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// The dashboard page, with its scripts and styles inlined
+const DASHBOARD_HTML: &str = include_str!("serve/dashboard.html");
+
+/// How often an idle event stream sends a keep-alive comment
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Every worktree pair with its (possibly suppressed) conflicting files
+fn build_matrix(snapshot: &Snapshot, all: bool) -> MatrixOutput {
+    let mut pairs = snapshot.pairs.clone();
+    let suppressed = apply_suppressions(&snapshot.worktrees, &mut pairs, all);
+    MatrixOutput {
+        schema_version: SCHEMA_VERSION,
+        generation: snapshot.generation,
+        worktrees: snapshot.worktrees.iter().map(|wt| wt.id.clone()).collect(),
+        pairs: pairs.into_iter().map(ConflictInfo::from).collect(),
+        suppressed,
+    }
+}
+
+// ============================================================================
+// Server
+// ============================================================================
+
+/// Run the HTTP server on localhost until killed.
+///
+/// With `open_dashboard`, also opens the dashboard page in a browser.
+pub fn run_serve(
+    worktrees: clash_sh::WorktreeManager,
+    port: u16,
+    open_dashboard: bool,
+) -> Result<(), String> {
+    let live = LiveState::start(worktrees).map_err(|e| e.to_string())?;
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("failed to listen on port {}: {}", port, e))?;
+    let url = format!("http://127.0.0.1:{}", port);
+    println!("clash API listening on {}", url);
+    if open_dashboard {
+        println!("Dashboard: {}/", url);
+        open_in_browser(&url);
+    }
+
+    for request in server.incoming_requests() {
+        let live = live.clone();
+        // Event streams stay open indefinitely, so every request gets a thread
+        thread::spawn(move || handle_request(request, &live, port));
+    }
+    Ok(())
+}
+
+/// Best-effort attempt to open a URL in the default browser
+fn open_in_browser(url: &str) {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    // Failing to launch a browser is fine; the URL is printed above
+    let _ = std::process::Command::new(program)
+        .arg(url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
+
+/// Route one request and send its response
+fn handle_request(request: Request, live: &LiveState, port: u16) {
+    if request.method() != &Method::Get {
+        respond(request, 405, &ErrorOutput::new("only GET is supported"));
+        return;
+    }
+
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let path = path.to_string();
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str().to_string())
+    };
+    if !is_local_host(header("Host").as_deref(), port) {
+        respond(request, 403, &ErrorOutput::new("unexpected Host header"));
+        return;
+    }
+    if path.starts_with("/api/")
+        && header("Origin").is_some_and(|origin| !is_local_origin(&origin, port))
+    {
+        respond(
+            request,
+            403,
+            &ErrorOutput::new("cross-origin requests are refused"),
+        );
+        return;
+    }
+    let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let all = params
+        .get("all")
+        .is_some_and(|v| v.is_empty() || v == "true" || v == "1");
+
+    let snapshot = live.snapshot();
+    let result = match path.as_str() {
+        "/" => {
+            let response = Response::from_string(DASHBOARD_HTML).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
+                    .expect("static header is valid"),
+            );
+            if let Err(e) = request.respond(response) {
+                eprintln!("Failed to send response: {}", e);
+            }
+            return;
+        }
+        "/api/worktrees" => to_value(&WorktreesOutput::new(snapshot.worktrees.all().to_vec())),
+        "/api/worktree" => worktree_endpoint(&snapshot, &params),
+        "/api/matrix" => to_value(&build_matrix(&snapshot, all)),
+        "/api/pair" => pair_endpoint(&snapshot, &params, all),
+        "/api/status" => {
+            let mut pairs = snapshot.pairs.clone();
+            apply_suppressions(&snapshot.worktrees, &mut pairs, all);
+            to_value(&build_status_output(&snapshot.worktrees, pairs))
+        }
+        "/api/check" => check_endpoint(&snapshot, &params, all),
+        "/api/events" => {
+            if let Err(e) = stream_events(request, live, all) {
+                eprintln!("Event stream error: {}", e);
+            }
+            return;
+        }
+        _ => Err((404, format!("no such endpoint '{}'", path))),
+    };
+
+    match result {
+        Ok(value) => respond(request, 200, &value),
+        Err((status, msg)) => respond(request, status, &ErrorOutput::new(msg)),
+    }
+}
+
+/// Whether a `Host` header names this server on the loopback interface
+fn is_local_host(host: Option<&str>, port: u16) -> bool {
+    let Some(host) = host else {
+        return false;
+    };
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, p)) => (name, p.parse().ok()),
+        None => (host, Some(80)),
+    };
+    matches!(name, "localhost" | "127.0.0.1") && host_port == Some(port)
+}
+
+/// Whether an `Origin` header is this server itself (the dashboard page)
+fn is_local_origin(origin: &str, port: u16) -> bool {
+    origin
+        .strip_prefix("http://")
+        .is_some_and(|host| is_local_host(Some(host), port))
+}
+
+fn required<'a>(params: &'a HashMap<String, String>, name: &str) -> Result<&'a str, (u16, String)> {
+    params
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| (400, format!("missing query parameter '{}'", name)))
+}
+
+fn worktree_endpoint(
+    snapshot: &Snapshot,
+    params: &HashMap<String, String>,
+) -> Result<Value, (u16, String)> {
+    let worktrees = &snapshot.worktrees;
+    let wt = worktrees
+        .find(required(params, "id")?)
+        .map_err(|e| (404, e.to_string()))?;
+
+    let registry = Registry::open(worktrees);
+    let claims: Vec<_> = registry
+        .claims()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.worktree == wt.id)
+        .collect();
+    let plans: Vec<_> = registry
+        .plans()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.worktree == wt.id)
+        .collect();
+    let uncommitted_files = wt.uncommitted_files().unwrap_or_default();
+
+    to_value(&WorktreeDetail {
+        schema_version: SCHEMA_VERSION,
+        worktree: wt.clone(),
+        head: wt.head_commit(),
+        uncommitted_files,
+        claims,
+        plans,
+    })
+}
+
+/// A pair's conflicting files, each with both sides' hunks against the merge base
+fn pair_endpoint(
+    snapshot: &Snapshot,
+    params: &HashMap<String, String>,
+    all: bool,
+) -> Result<Value, (u16, String)> {
+    let worktrees = &snapshot.worktrees;
+    let find = |name| worktrees.find(name).map_err(|e| (404, e.to_string()));
+    let (wt1, wt2) = (
+        find(required(params, "wt1")?)?,
+        find(required(params, "wt2")?)?,
+    );
+
+    let mut pairs: Vec<_> = snapshot
+        .pairs
+        .iter()
+        .filter(|p| {
+            (p.wt1.id == wt1.id && p.wt2.id == wt2.id) || (p.wt1.id == wt2.id && p.wt2.id == wt1.id)
+        })
+        .cloned()
+        .collect();
+    apply_suppressions(worktrees, &mut pairs, all);
+    let pair = pairs
+        .pop()
+        .ok_or((404, "no results for this pair".to_string()))?;
+
+    let files = pair
+        .conflicting_files
+        .iter()
+        .map(|file| match wt1.diff_file_against(wt2, file, Side::Head) {
+            Ok(diff) => FileDivergence::from(diff),
+            Err(e) => FileDivergence {
+                path: file.clone(),
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        })
+        .collect();
+
+    to_value(&PairOutput {
+        schema_version: SCHEMA_VERSION,
+        wt1: wt1.id.clone(),
+        wt2: wt2.id.clone(),
+        files,
+        error: pair.error,
+    })
+}
+
+fn check_endpoint(
+    snapshot: &Snapshot,
+    params: &HashMap<String, String>,
+    all: bool,
+) -> Result<Value, (u16, String)> {
+    let path = required(params, "path")?;
+    let suppressions = if all {
+        Suppressions::none()
+    } else {
+        Suppressions::load(&snapshot.worktrees)
+    };
+    let output = check::check_file(
+        &snapshot.worktrees,
+        path,
+        params.get("session").map(String::as_str),
+        &suppressions,
+        Some(&snapshot.pairs),
+        None,
+    )
+    .map_err(|e| (422, e.to_string()))?;
+    to_value(&output)
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, (u16, String)> {
+    serde_json::to_value(value).map_err(|e| (500, e.to_string()))
+}
+
+fn respond(request: Request, status: u16, body: &impl Serialize) {
+    let body = serde_json::to_string(body).unwrap_or_default();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(json_header());
+    if let Err(e) = request.respond(response) {
+        eprintln!("Failed to send response: {}", e);
+    }
+}
+
+fn json_header() -> Header {
+    Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid")
+}
+
+// ============================================================================
+// Server-Sent Events
+// ============================================================================
+
+/// Stream matrix changes until the client disconnects.
+///
+/// Writes the response by hand on the raw connection so every event is
+/// flushed immediately rather than buffered into chunks.
+fn stream_events(request: Request, live: &LiveState, all: bool) -> io::Result<()> {
+    // Subscribe before taking the initial matrix so no refresh is missed
+    let updates = live.subscribe();
+    let mut matrix = build_matrix(&live.snapshot(), all);
+
+    let mut writer = request.into_writer();
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/event-stream\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: close\r\n\r\n"
+    )?;
+    send_event(&mut writer, "matrix", &matrix)?;
+
+    loop {
+        match updates.recv_timeout(KEEPALIVE_INTERVAL) {
+            Ok(snapshot) => {
+                let next = build_matrix(&snapshot, all);
+                let changes = pair_changes(&matrix.pairs, &next.pairs);
+                if !changes.is_empty() || next.worktrees != matrix.worktrees {
+                    let event = ChangeEvent {
+                        schema_version: SCHEMA_VERSION,
+                        generation: next.generation,
+                        worktrees: next.worktrees.clone(),
+                        changes,
+                    };
+                    send_event(&mut writer, "change", &event)?;
+                }
+                matrix = next;
+            }
+            // Comments keep proxies from timing out and reveal closed connections
+            Err(RecvTimeoutError::Timeout) => {
+                writer.write_all(b": keep-alive\n\n")?;
+                writer.flush()?;
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+fn send_event<T: Serialize>(writer: &mut impl Write, name: &str, data: &T) -> io::Result<()> {
+    let data = serde_json::to_string(data).map_err(io::Error::other)?;
+    write!(writer, "event: {}\ndata: {}\n\n", name, data)?;
+    writer.flush()
+}
+
+/// Diff two pair lists, reporting only pairs whose conflicts changed.
+///
+/// Pairs are matched by worktree ids; a pair that appears or disappears
+/// (a worktree was added or removed) is compared against an empty pair.
+fn pair_changes(before: &[ConflictInfo], after: &[ConflictInfo]) -> Vec<PairChange> {
+    let find = |pairs: &[ConflictInfo], pair: &ConflictInfo| {
+        pairs
+            .iter()
+            .find(|p| p.wt1_id == pair.wt1_id && p.wt2_id == pair.wt2_id)
+            .cloned()
+    };
+    let empty = |pair: &ConflictInfo| ConflictInfo {
+        conflicting_files: Vec::new(),
+        error: None,
+        ..pair.clone()
+    };
+
+    let mut changes = Vec::new();
+    let updated = after
+        .iter()
+        .map(|new| (find(before, new).unwrap_or_else(|| empty(new)), new.clone()));
+    let removed = before
+        .iter()
+        .filter(|old| find(after, old).is_none())
+        .map(|old| (old.clone(), empty(old)));
+
+    for (old, new) in updated.chain(removed) {
+        if old == new {
+            continue;
+        }
+        let added: Vec<String> = new
+            .conflicting_files
+            .iter()
+            .filter(|f| !old.conflicting_files.contains(f))
+            .cloned()
+            .collect();
+        let resolved: Vec<String> = old
+            .conflicting_files
+            .iter()
+            .filter(|f| !new.conflicting_files.contains(f))
+            .cloned()
+            .collect();
+        if added.is_empty() && resolved.is_empty() && old.error == new.error {
+            continue;
+        }
+        changes.push(PairChange {
+            wt1_id: new.wt1_id,
+            wt2_id: new.wt2_id,
+            added,
+            resolved,
+            error: new.error,
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(wt1: &str, wt2: &str, files: &[&str]) -> ConflictInfo {
+        ConflictInfo {
+            wt1_id: wt1.to_string(),
+            wt2_id: wt2.to_string(),
+            conflicting_files: files.iter().map(|f| f.to_string()).collect(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn only_local_hosts_and_origins_are_served() {
+        assert!(is_local_host(Some("127.0.0.1:7420"), 7420));
+        assert!(is_local_host(Some("localhost:7420"), 7420));
+        assert!(!is_local_host(Some("localhost:8080"), 7420));
+        assert!(!is_local_host(Some("attacker.example:7420"), 7420));
+        assert!(!is_local_host(None, 7420));
+
+        assert!(is_local_origin("http://localhost:7420", 7420));
+        assert!(!is_local_origin("https://localhost:7420", 7420));
+        assert!(!is_local_origin("http://attacker.example", 7420));
+        assert!(!is_local_origin("null", 7420));
+    }
+
+    #[test]
+    fn pair_changes_reports_added_resolved_and_removed_pairs() {
+        let before = vec![pair("a", "b", &["x.rs", "y.rs"]), pair("a", "c", &["z.rs"])];
+        let after = vec![pair("a", "b", &["y.rs", "w.rs"])];
+
+        let changes = pair_changes(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].added, vec!["w.rs"]);
+        assert_eq!(changes[0].resolved, vec!["x.rs"]);
+        assert_eq!(
+            (changes[1].wt2_id.as_str(), changes[1].resolved.len()),
+            ("c", 1)
+        );
+        assert!(pair_changes(&after, &after).is_empty());
+    }
+}
@@ -1,49 +1,38 @@
-use clash_sh::{WorktreeManager, WorktreePairConflict, WorktreeStatus};
+use crate::format::{self, Annotation, Level, OutputFormat, Rule};
+use clash_sh::intent;
+use clash_sh::output::{ConflictInfo, StatusOutput, WorktreeInfo};
+use clash_sh::{Side, Suppressions, WorktreeManager, WorktreePairConflict, WorktreeStatus};
 use colored::Colorize;
-use serde::Serialize;
 use std::collections::HashMap;
 
-/// JSON output structure for status command
-#[derive(Debug, Serialize)]
-struct StatusOutput {
-    worktrees: Vec<WorktreeInfo>,
-    conflicts: Vec<ConflictInfo>,
-}
-
-/// Worktree information for JSON output (simplified from full Worktree struct)
-#[derive(Debug, Serialize)]
-struct WorktreeInfo {
-    id: String,
-    path: String,
-    branch: String,
-    status: WorktreeStatus,
-}
-
-/// Conflict information for JSON output (references worktrees by ID)
-#[derive(Debug, Serialize)]
-struct ConflictInfo {
-    wt1_id: String,
-    wt2_id: String,
-    conflicting_files: Vec<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
-}
-
 /// Handles the display of status information for worktrees and conflicts
 pub struct StatusDisplay<'a> {
     worktrees: &'a WorktreeManager,
+    all: bool,
 }
 
 impl<'a> StatusDisplay<'a> {
     /// Create a new StatusDisplay for the given worktrees
-    pub fn new(worktrees: &'a WorktreeManager) -> Self {
-        Self { worktrees }
+    ///
+    /// When `all` is false, ignored and acknowledged conflicts are hidden.
+    pub fn new(worktrees: &'a WorktreeManager, all: bool) -> Self {
+        Self { worktrees, all }
     }
 
-    /// Run the full status display (worktrees + conflicts)
+    /// Run the full status display (worktrees + conflicts + plan overlaps)
     pub fn show(&self) {
         self.show_worktrees();
         self.show_conflicts();
+        self.show_plan_overlaps();
+    }
+
+    /// Display overlaps between planned edits, if any were declared
+    pub fn show_plan_overlaps(&self) {
+        let overlaps = intent::find_overlaps(self.worktrees);
+        if !overlaps.is_empty() {
+            println!();
+            crate::plan::display_overlaps(&overlaps);
+        }
     }
 
     /// Display all worktrees in a formatted list
@@ -92,7 +81,7 @@ impl<'a> StatusDisplay<'a> {
             .italic()
         );
 
-        let pair_results = self.worktrees.check_all_conflicts();
+        let (pair_results, suppressed) = check_pairs(self.worktrees, self.all);
         let conflict_matrix = self.build_conflict_matrix(&pair_results);
 
         // Display as table
@@ -102,11 +91,14 @@ impl<'a> StatusDisplay<'a> {
         self.display_detailed_conflicts(&pair_results, &conflict_matrix);
 
         // Display summary
-        self.display_summary(&pair_results);
+        self.display_summary(&pair_results, suppressed);
     }
 
-    /// Build a conflict matrix from pair results
-    fn build_conflict_matrix(
+    /// Build a conflict matrix from pair results.
+    ///
+    /// Cells are indexed like the worktree list and hold the conflicting
+    /// files, or `None` on the diagonal and for pairs that failed to check.
+    pub fn build_conflict_matrix(
         &self,
         pair_results: &[WorktreePairConflict],
     ) -> Vec<Vec<Option<Vec<String>>>> {
@@ -295,7 +287,7 @@ impl<'a> StatusDisplay<'a> {
     }
 
     /// Display summary statistics
-    fn display_summary(&self, pair_results: &[WorktreePairConflict]) {
+    fn display_summary(&self, pair_results: &[WorktreePairConflict], suppressed: usize) {
         if pair_results.is_empty() {
             return;
         }
@@ -337,6 +329,13 @@ impl<'a> StatusDisplay<'a> {
                 if error_count == 1 { "pair" } else { "pairs" }
             );
         }
+
+        if suppressed > 0 {
+            println!(
+                "  {} suppressed by .clashignore or acks (use --all to show)",
+                suppressed.to_string().bright_black()
+            );
+        }
     }
 
     /// Truncate branch name to fit in column
@@ -378,46 +377,143 @@ impl<'a> StatusDisplay<'a> {
     }
 }
 
+/// Check all worktree pairs, hiding suppressed conflicts unless `all` is set.
+///
+/// Uses a running daemon's cached results when available. Returns the pair
+/// results and the number of conflicting files hidden.
+pub fn check_pairs(worktrees: &WorktreeManager, all: bool) -> (Vec<WorktreePairConflict>, usize) {
+    let mut pair_results =
+        crate::daemon::cached_pairs(worktrees).unwrap_or_else(|| worktrees.check_all_conflicts());
+    let suppressed = apply_suppressions(worktrees, &mut pair_results, all);
+    (pair_results, suppressed)
+}
+
+/// Hide suppressed conflicts in already-computed pair results unless `all` is set.
+///
+/// Returns the number of conflicting files hidden.
+pub fn apply_suppressions(
+    worktrees: &WorktreeManager,
+    pair_results: &mut [WorktreePairConflict],
+    all: bool,
+) -> usize {
+    if all {
+        0
+    } else {
+        Suppressions::load(worktrees).apply(pair_results)
+    }
+}
+
 /// Run the status command - displays worktrees and checks for conflicts
-pub fn run_status(worktrees: &WorktreeManager, json: bool) {
-    if json {
-        // Build JSON output
-        let worktree_infos: Vec<WorktreeInfo> = worktrees
-            .iter()
-            .map(|wt| WorktreeInfo {
-                id: wt.id.clone(),
-                path: wt.path.display().to_string(),
-                branch: wt.branch.clone(),
-                status: wt.status,
-            })
-            .collect();
+pub fn run_status(worktrees: &WorktreeManager, format: OutputFormat, all: bool) {
+    match format {
+        OutputFormat::Text => {
+            // Human-readable output
+            let display = StatusDisplay::new(worktrees, all);
+            display.show();
+        }
+        OutputFormat::Json => {
+            let output = build_status_output(worktrees, check_pairs(worktrees, all).0);
 
-        // Check conflicts and convert to minimal format
-        let conflicts: Vec<ConflictInfo> = worktrees
-            .check_all_conflicts()
-            .into_iter()
-            .filter(|c| !c.conflicting_files.is_empty() || c.error.is_some())
-            .map(|c| ConflictInfo {
-                wt1_id: c.wt1.id,
-                wt2_id: c.wt2.id,
-                conflicting_files: c.conflicting_files,
-                error: c.error,
-            })
-            .collect();
+            // Output JSON
+            match serde_json::to_string_pretty(&output) {
+                Ok(json_str) => println!("{}", json_str),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            }
+        }
+        _ => {
+            let pair_results = check_pairs(worktrees, all).0;
+            let suites: Vec<String> = pair_results.iter().map(pair_name).collect();
+            let annotations = status_annotations(worktrees, &pair_results);
+            println!("{}", format::render(format, &suites, &annotations));
+        }
+    }
+}
 
-        let output = StatusOutput {
-            worktrees: worktree_infos,
-            conflicts,
-        };
+/// Annotations for every conflicting file of every pair, located in the
+/// first worktree's committed version of the file
+fn status_annotations(
+    worktrees: &WorktreeManager,
+    pair_results: &[WorktreePairConflict],
+) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    for pair in pair_results {
+        let suite = pair_name(pair);
+        if let Some(error) = &pair.error {
+            annotations.push(Annotation {
+                suite,
+                rule: Rule::CheckFailed,
+                level: Level::Warning,
+                file: None,
+                lines: None,
+                title: "Conflict check failed".to_string(),
+                message: error.clone(),
+            });
+            continue;
+        }
 
-        // Output JSON
-        match serde_json::to_string_pretty(&output) {
-            Ok(json_str) => println!("{}", json_str),
-            Err(e) => eprintln!("Error serializing to JSON: {}", e),
+        let title = format!("Merge conflict with {} [{}]", pair.wt2.id, pair.wt2.branch);
+        let message = format!(
+            "Merging {} [{}] and {} [{}] conflicts on this file",
+            pair.wt1.id, pair.wt1.branch, pair.wt2.id, pair.wt2.branch
+        );
+        // Merge conflicts are computed on HEAD, so locate them there too
+        let ours = worktrees.find(&pair.wt1.id).ok();
+        let theirs = worktrees.find(&pair.wt2.id).ok();
+        for file in &pair.conflicting_files {
+            let ranges = match (ours, theirs) {
+                (Some(ours), Some(theirs)) => ours
+                    .diff_file_against(theirs, file, Side::Head)
+                    .map(|diff| format::conflict_lines(&diff))
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            let ranges = if ranges.is_empty() {
+                vec![None]
+            } else {
+                ranges.into_iter().map(Some).collect()
+            };
+            for lines in ranges {
+                annotations.push(Annotation {
+                    suite: suite.clone(),
+                    rule: Rule::MergeConflict,
+                    level: Level::Error,
+                    file: Some(file.clone()),
+                    lines,
+                    title: title.clone(),
+                    message: message.clone(),
+                });
+            }
         }
-    } else {
-        // Human-readable output
-        let display = StatusDisplay::new(worktrees);
-        display.show();
     }
+    annotations
+}
+
+/// Name of a worktree pair, e.g. `main vs feature-a`
+fn pair_name(pair: &WorktreePairConflict) -> String {
+    format!("{} vs {}", pair.wt1.id, pair.wt2.id)
+}
+
+/// Build the JSON status document from already-computed pair results
+pub fn build_status_output(
+    worktrees: &WorktreeManager,
+    pair_results: Vec<WorktreePairConflict>,
+) -> StatusOutput {
+    let worktree_infos: Vec<WorktreeInfo> = worktrees
+        .iter()
+        .map(|wt| WorktreeInfo {
+            id: wt.id.clone(),
+            path: wt.path.display().to_string(),
+            branch: wt.branch.clone(),
+            status: wt.status,
+        })
+        .collect();
+
+    // Convert conflicts to minimal format
+    let conflicts: Vec<ConflictInfo> = pair_results
+        .into_iter()
+        .filter(|c| !c.conflicting_files.is_empty() || c.error.is_some())
+        .map(ConflictInfo::from)
+        .collect();
+
+    StatusOutput::new(worktree_infos, conflicts, intent::find_overlaps(worktrees))
 }
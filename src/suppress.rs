@@ -0,0 +1,150 @@
+//! Suppression of known and accepted conflicts
+//!
+//! Two mechanisms hide conflicts from reports:
+//!
+//! - **Ignore patterns** - gitignore-style patterns in a `.clashignore` file
+//!   at the main worktree root. Matching paths are never reported, for any pair.
+//! - **Acks** - a specific path accepted for a specific pair via `clash ack`,
+//!   valid until either side's blob for that path changes.
+
+use crate::registry::{Ack, Registry};
+use crate::{Worktree, WorktreeManager, WorktreePairConflict};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Name of the ignore file at the main worktree root
+pub const IGNORE_FILE: &str = ".clashignore";
+
+/// Ignore patterns and acks loaded for a repository
+#[derive(Debug, Clone)]
+pub struct Suppressions {
+    ignore: Gitignore,
+    acks: Vec<Ack>,
+}
+
+impl Suppressions {
+    /// Load ignore patterns and acks for the repository.
+    ///
+    /// A missing ignore file or registry simply means nothing is suppressed.
+    /// Malformed patterns and unreadable ack files are skipped rather than
+    /// failing the command, since suppression is best-effort.
+    pub fn load(worktrees: &WorktreeManager) -> Self {
+        let ignore = worktrees
+            .main()
+            .map(|main| {
+                let mut builder = GitignoreBuilder::new(&main.path);
+                let _ = builder.add(main.path.join(IGNORE_FILE));
+                builder.build().unwrap_or_else(|_| Gitignore::empty())
+            })
+            .unwrap_or_else(Gitignore::empty);
+
+        let acks = Registry::open(worktrees).acks().unwrap_or_default();
+
+        Self { ignore, acks }
+    }
+
+    /// Suppressions that hide nothing (used for `--all`)
+    pub fn none() -> Self {
+        Self {
+            ignore: Gitignore::empty(),
+            acks: Vec::new(),
+        }
+    }
+
+    /// Check whether a repo-relative path matches an ignore pattern
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.ignore
+            .matched_path_or_any_parents(path, false)
+            .is_ignore()
+    }
+
+    /// Check whether a path has a current ack for the given pair
+    pub fn is_acked(&self, a: &Worktree, b: &Worktree, path: &str) -> bool {
+        self.acks
+            .iter()
+            .any(|ack| ack.matches(&a.id, &b.id, path) && ack.is_current(a, b))
+    }
+
+    /// Check whether a conflict on `path` between two worktrees is suppressed
+    pub fn is_suppressed(&self, a: &Worktree, b: &Worktree, path: &str) -> bool {
+        self.is_ignored(path) || self.is_acked(a, b, path)
+    }
+
+    /// Remove suppressed files from pair results.
+    ///
+    /// Returns the number of conflicting files that were hidden.
+    pub fn apply(&self, results: &mut [WorktreePairConflict]) -> usize {
+        let mut hidden = 0;
+        for result in results.iter_mut() {
+            let before = result.conflicting_files.len();
+            let (wt1, wt2) = (&result.wt1, &result.wt2);
+            result
+                .conflicting_files
+                .retain(|f| !self.is_suppressed(wt1, wt2, f));
+            hidden += before - result.conflicting_files.len();
+        }
+        hidden
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    /// Both sides change `a.txt` and `gen/out.txt` on the same line
+    fn conflicting_repo() -> TestRepo {
+        let repo = TestRepo::new(&[("a.txt", "base\n"), ("gen/out.txt", "base\n")]);
+        repo.add_worktree("feature");
+        for (wt, content) in [("main", "ours\n"), ("feature", "theirs\n")] {
+            repo.write(wt, "a.txt", content);
+            repo.write(wt, "gen/out.txt", content);
+            repo.commit(wt, "change");
+        }
+        repo
+    }
+
+    #[test]
+    fn ignore_patterns_and_acks_hide_conflicts() {
+        let repo = conflicting_repo();
+        repo.write("main", IGNORE_FILE, "gen/\n");
+        let worktrees = repo.manager();
+        let (main, feature) = (&worktrees.all()[0], &worktrees.all()[1]);
+        Registry::open(&worktrees)
+            .add_ack(Ack::new(feature, main, "a.txt"))
+            .unwrap();
+
+        let suppressions = Suppressions::load(&worktrees);
+        assert!(suppressions.is_suppressed(main, feature, "gen/out.txt"));
+        assert!(suppressions.is_suppressed(main, feature, "a.txt"));
+        assert!(!suppressions.is_suppressed(main, feature, "b.txt"));
+
+        let mut results = worktrees.check_all_conflicts();
+        assert_eq!(results[0].conflicting_files.len(), 2);
+        assert_eq!(suppressions.apply(&mut results), 2);
+        assert!(results[0].conflicting_files.is_empty());
+        assert_eq!(
+            Suppressions::none().apply(&mut worktrees.check_all_conflicts()),
+            0
+        );
+    }
+
+    #[test]
+    fn ack_expires_when_a_side_commits_a_new_blob() {
+        let repo = conflicting_repo();
+        let worktrees = repo.manager();
+        let (main, feature) = (&worktrees.all()[0], &worktrees.all()[1]);
+        Registry::open(&worktrees)
+            .add_ack(Ack::new(main, feature, "a.txt"))
+            .unwrap();
+        assert!(Suppressions::load(&worktrees).is_acked(main, feature, "a.txt"));
+
+        // Uncommitted edits don't touch HEAD, so the ack holds
+        repo.write("feature", "a.txt", "theirs, again\n");
+        assert!(Suppressions::load(&worktrees).is_acked(main, feature, "a.txt"));
+
+        repo.commit("feature", "change again");
+        let mut results = worktrees.check_all_conflicts();
+        assert_eq!(Suppressions::load(&worktrees).apply(&mut results), 0);
+        assert!(!Suppressions::load(&worktrees).is_acked(main, feature, "a.txt"));
+    }
+}
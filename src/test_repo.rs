@@ -0,0 +1,91 @@
+//! Throwaway git repositories with linked worktrees, for tests
+//!
+//! Shared by the library's and the binary's tests, which each use only part
+//! of it.
+#![allow(dead_code)]
+
+use crate::WorktreeManager;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A repository in a temporary directory: the main worktree on `main`, and
+/// linked worktrees next to it, each on a branch named after it
+pub struct TestRepo {
+    dir: tempfile::TempDir,
+}
+
+impl TestRepo {
+    /// A repository whose first commit has `files` (path, content)
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let repo = Self {
+            dir: tempfile::tempdir().expect("create temp dir"),
+        };
+        std::fs::create_dir(repo.path("main")).expect("create main worktree");
+        repo.git("main", &["init", "-q", "-b", "main"]);
+        for (path, content) in files {
+            repo.write("main", path, content);
+        }
+        repo.commit("main", "initial");
+        repo
+    }
+
+    /// Add a linked worktree on a new branch `name` from main's HEAD
+    pub fn add_worktree(&self, name: &str) {
+        let path = self.path(name);
+        self.git(
+            "main",
+            &["worktree", "add", "-q", "-b", name, path.to_str().unwrap()],
+        );
+    }
+
+    /// Root of a worktree (`main` or a linked worktree's name)
+    pub fn path(&self, worktree: &str) -> PathBuf {
+        self.dir.path().join(worktree)
+    }
+
+    /// Write a file in a worktree without staging it
+    pub fn write(&self, worktree: &str, file: &str, content: &str) {
+        let path = self.path(worktree).join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("create parent dirs");
+        }
+        std::fs::write(path, content).expect("write file");
+    }
+
+    /// Stage everything in a worktree and commit it
+    pub fn commit(&self, worktree: &str, message: &str) {
+        self.git(worktree, &["add", "-A"]);
+        self.git(worktree, &["commit", "-q", "--allow-empty", "-m", message]);
+    }
+
+    /// Run git in a worktree, panicking if it fails; returns stdout
+    pub fn git(&self, worktree: &str, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(self.path(worktree))
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .output()
+            .expect("run git");
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    pub fn manager(&self) -> WorktreeManager {
+        WorktreeManager::discover_from(self.path("main").to_str().unwrap())
+            .expect("discover worktrees")
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+}
@@ -6,6 +6,7 @@ mod ui;
 mod watcher;
 
 pub use state::WatchState;
+pub(crate) use watcher::create_watcher;
 
 use crossterm::{
     execute,
@@ -15,7 +16,9 @@ use ratatui::{Terminal, backend::CrosstermBackend};
 use std::io;
 
 /// Entry point for watch mode - sets up terminal and runs the TUI
-pub fn run_watch_mode(worktrees: clash_sh::WorktreeManager) -> Result<(), io::Error> {
+///
+/// When `all` is false, ignored and acknowledged conflicts are hidden.
+pub fn run_watch_mode(worktrees: clash_sh::WorktreeManager, all: bool) -> Result<(), io::Error> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -24,7 +27,7 @@ pub fn run_watch_mode(worktrees: clash_sh::WorktreeManager) -> Result<(), io::Er
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state with initial worktrees
-    let mut state = WatchState::with_worktrees(worktrees);
+    let mut state = WatchState::with_worktrees(worktrees, all);
 
     // Run the app (CTRL+C is handled as a keyboard event in raw mode)
     let res = app::run_app(&mut terminal, &mut state);
@@ -1,6 +1,8 @@
 //! Application state for watch mode
 
-use clash_sh::WorktreeManager;
+use clash_sh::intent::{self, PlanOverlap};
+use clash_sh::registry::Registry;
+use clash_sh::{Suppressions, WorktreeManager};
 use std::collections::VecDeque;
 
 /// Maximum number of events to keep in memory
@@ -12,16 +14,23 @@ pub struct WatchState {
     pub conflicts: Vec<(String, String, Vec<String>)>, // (wt1, wt2, files)
     pub events: VecDeque<String>,                      // Changed to VecDeque for efficient removal
     pub events_scroll: Option<usize>, // None = stick to bottom, Some(n) = show from event n
+    pub show_all: bool,               // Include ignored and acknowledged conflicts
+    pub plan_overlaps: Vec<PlanOverlap>,
+    /// Highest message id already shown in the event log
+    last_message_id: Option<u64>,
 }
 
 impl WatchState {
     /// Create WatchState with initial worktrees
-    pub fn with_worktrees(worktrees: WorktreeManager) -> Self {
+    pub fn with_worktrees(worktrees: WorktreeManager, show_all: bool) -> Self {
         let mut state = Self {
             worktrees,
             conflicts: Vec::new(),
             events: VecDeque::with_capacity(MAX_EVENTS),
             events_scroll: None, // None = stick to bottom
+            show_all,
+            plan_overlaps: Vec::new(),
+            last_message_id: None,
         };
 
         state.add_event("Watch mode started".to_string());
@@ -73,6 +82,13 @@ impl WatchState {
         // Collect errors to add to events later
         let mut errors = Vec::new();
 
+        // Reload suppressions each time since acks can be added while watching
+        let suppressions = if self.show_all {
+            Suppressions::none()
+        } else {
+            Suppressions::load(&self.worktrees)
+        };
+
         // Check all pairs of worktrees for conflicts
         {
             let all = self.worktrees.all();
@@ -82,7 +98,8 @@ impl WatchState {
                     let wt2 = &all[j];
 
                     match wt1.conflicts_with(wt2) {
-                        Ok(conflicting_files) => {
+                        Ok(mut conflicting_files) => {
+                            conflicting_files.retain(|f| !suppressions.is_suppressed(wt1, wt2, f));
                             if !conflicting_files.is_empty() {
                                 self.conflicts.push((
                                     wt1.branch.clone(),
@@ -106,6 +123,38 @@ impl WatchState {
         for error in errors {
             self.add_event(error);
         }
+
+        self.plan_overlaps = intent::find_overlaps(&self.worktrees);
+        self.check_messages();
+    }
+
+    /// Log messages posted since the last check.
+    ///
+    /// On the first check, existing messages are only counted so the log
+    /// isn't flooded with old ones.
+    fn check_messages(&mut self) {
+        let Ok(messages) = Registry::open(&self.worktrees).messages() else {
+            return;
+        };
+        let newest = messages.iter().map(|m| m.id).max().unwrap_or(0);
+        match self.last_message_id {
+            None if !messages.is_empty() => {
+                self.add_event(format!("{} message(s) on the board", messages.len()));
+            }
+            None => {}
+            Some(last) => {
+                for m in messages.iter().filter(|m| m.id > last) {
+                    self.add_event(format!(
+                        "Message #{} from {} ({}): {}",
+                        m.id,
+                        m.from,
+                        crate::msg::describe_recipient(&m.to),
+                        m.body
+                    ));
+                }
+            }
+        }
+        self.last_message_id = Some(newest);
     }
 
     /// Count unique files affected by conflicts
@@ -135,6 +135,22 @@ fn render_conflicts(f: &mut Frame, area: Rect, state: &WatchState) {
         lines
     };
 
+    if !state.plan_overlaps.is_empty() {
+        conflict_text.push(Line::from(""));
+        conflict_text.push(Line::from(Span::styled(
+            format!("◆ {} planned overlap(s)", state.plan_overlaps.len()),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for o in &state.plan_overlaps {
+            conflict_text.push(Line::from(format!(
+                "  {} ↔ {}: {} ({})",
+                o.planned_by, o.other, o.other_path, o.kind
+            )));
+        }
+    }
+
     // Add legend explaining conflict detection basis
     conflict_text.push(Line::from(""));
     conflict_text.push(Line::from(""));
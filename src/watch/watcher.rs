@@ -1,6 +1,7 @@
 //! File system watcher with gitignore filtering
 
 use super::state::WatchState;
+use clash_sh::WorktreeManager;
 use ignore::gitignore::Gitignore;
 use notify::event::ModifyKind;
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
@@ -19,24 +20,39 @@ pub fn setup_watcher(
     state: &mut WatchState,
     tx: mpsc::Sender<String>,
 ) -> io::Result<RecommendedWatcher> {
+    let (watcher, warnings) = create_watcher(&state.worktrees, tx)?;
+    for warning in warnings {
+        state.add_event(warning);
+    }
+    state.add_event(format!("Watching {} directories", state.worktrees.len()));
+    Ok(watcher)
+}
+
+/// Create a watcher over all worktree directories that sends an event
+/// marker ([`EVENT_TYPE_GIT`] or [`EVENT_TYPE_FILE`]) for each relevant change.
+///
+/// Returns the watcher (which stops watching when dropped) and any
+/// non-fatal warnings encountered while setting it up.
+pub fn create_watcher(
+    worktrees: &WorktreeManager,
+    tx: mpsc::Sender<String>,
+) -> io::Result<(RecommendedWatcher, Vec<String>)> {
+    let mut warnings = Vec::new();
+
     // Load .gitignore from repository root for filtering
     // All worktrees share the same repo, so we use the first worktree's path
-    let (gitignore, repo_root) = if let Some(first_wt) = state.worktrees.main() {
+    let (gitignore, repo_root) = if let Some(first_wt) = worktrees.main() {
         let repo_root = first_wt.path.clone();
         let gitignore_path = repo_root.join(".gitignore");
         let (gi, err) = Gitignore::new(&gitignore_path);
         if let Some(e) = err {
-            state.add_event(format!("Warning loading .gitignore: {}", e));
+            warnings.push(format!("Warning loading .gitignore: {}", e));
         }
         (gi, repo_root)
     } else {
         (Gitignore::empty(), PathBuf::new())
     };
 
-    // Clone for the watcher callback
-    let gitignore = gitignore.clone();
-    let repo_root = repo_root.clone();
-
     // Create watcher with event filtering callback
     let mut watcher = RecommendedWatcher::new(
         move |res: Result<notify::Event, notify::Error>| {
@@ -62,14 +78,13 @@ pub fn setup_watcher(
     .map_err(io::Error::other)?;
 
     // Watch all worktree directories
-    for worktree in state.worktrees.iter() {
+    for worktree in worktrees.iter() {
         watcher
             .watch(&worktree.path, RecursiveMode::Recursive)
             .map_err(io::Error::other)?;
     }
-    state.add_event(format!("Watching {} directories", state.worktrees.len()));
 
-    Ok(watcher)
+    Ok((watcher, warnings))
 }
 
 /// Determine if a file system event should trigger a conflict refresh
@@ -98,6 +113,7 @@ fn should_process_event(event: &notify::Event, gitignore: &Gitignore, repo_root:
                    path_str.contains("/.git/refs/") ||       // Commits (branch updates)
                    path_str.contains("/refs/") && path_str.ends_with(".lock") ||  // Commit in progress
                    path_str.contains("/.git/MERGE_HEAD") ||  // Merge in progress
+                   path_str.ends_with("/clash/messages.json") || // Messages between agents
                    path_str.contains("/.git/REBASE_HEAD"); // Rebase in progress
         }
 
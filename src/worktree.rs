@@ -4,14 +4,20 @@
 //! and tracking their status (clean, dirty, conflicted, etc.). It also includes
 //! conflict detection using git merge-tree analysis.
 
+mod changes;
 mod conflict;
+mod content;
 mod error;
+mod hunks;
 mod manager;
 
-pub use conflict::WorktreePairConflict;
+pub use conflict::{SequenceConflict, WorktreePairConflict};
+pub use content::CommitInfo;
 pub use error::{Result as WorktreeResult, WorktreeError};
+pub use hunks::{ConflictRegion, DivergenceKind, Hunk, PairFileDiff, Side, diff_lines};
 pub use manager::WorktreeManager;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -25,7 +31,7 @@ pub(crate) const DETACHED_HEAD_LABEL: &str = "(detached HEAD)";
 pub(crate) const INACCESSIBLE_PATH_LABEL: &str = "(inaccessible)";
 
 /// A git worktree with its current state (simplified for MVP)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Worktree {
     /// Unique identifier (MAIN_WORKTREE_ID for main worktree, branch name for linked worktrees)
     pub id: String,
@@ -41,10 +47,13 @@ pub struct Worktree {
 }
 
 // Worktree methods are extended in submodules:
-// - conflict.rs: adds conflicts_with() and other conflict detection methods
+// - conflict.rs: adds conflicts_with(), file_conflicts_with(), revs_conflict() and other conflict detection methods
+// - content.rs: adds head_blob_id(), head_file_contents(), staged_file_contents(), head_commit() and recent_commits()
+// - changes.rs: adds changed_files(), uncommitted_files() and staged_files()
+// - hunks.rs: adds diff_file_against() for line-level pair diffs
 
 /// Status of a git worktree
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum WorktreeStatus {
     /// No uncommitted changes
@@ -0,0 +1,91 @@
+//! Changed-file listing for Worktree
+//!
+//! This module extends Worktree with methods that list which files a
+//! worktree has touched: committed since the merge base with another
+//! worktree, and uncommitted in its working directory.
+
+use super::Worktree;
+use super::conflict::{get_head_commit, get_tree_id};
+use super::error::{Result, WorktreeError};
+use std::collections::BTreeSet;
+
+impl Worktree {
+    /// List files changed in this worktree relative to its merge base with `other`.
+    ///
+    /// Includes both committed changes since the merge base and uncommitted
+    /// changes (staged, unstaged and untracked). Paths are repo-relative and sorted.
+    pub fn changed_files(&self, other: &Worktree) -> Result<Vec<String>> {
+        let mut files = self.committed_files_since_base(other)?;
+        files.extend(self.uncommitted_files()?);
+        Ok(files.into_iter().collect())
+    }
+
+    /// List files with uncommitted changes (staged, unstaged or untracked).
+    pub fn uncommitted_files(&self) -> Result<BTreeSet<String>> {
+        let repo = open(self)?;
+        let status = repo
+            .status(gix::progress::Discard)
+            .map_err(|e| WorktreeError::GitOperation(e.to_string()))?
+            .untracked_files(gix::status::UntrackedFiles::Files)
+            .into_iter(None)
+            .map_err(|e| WorktreeError::GitOperation(e.to_string()))?;
+
+        let mut files = BTreeSet::new();
+        for item in status {
+            let item = item.map_err(|e| WorktreeError::GitOperation(e.to_string()))?;
+            files.insert(item.location().to_string());
+        }
+        Ok(files)
+    }
+
+    /// List files whose staged version differs from HEAD, as the next commit
+    /// would record them. Renames count as a deletion and an addition.
+    pub fn staged_files(&self) -> Result<BTreeSet<String>> {
+        let repo = open(self)?;
+        let status = repo
+            .status(gix::progress::Discard)
+            .map_err(|e| WorktreeError::GitOperation(e.to_string()))?
+            .tree_index_track_renames(gix::status::tree_index::TrackRenames::Disabled)
+            .into_iter(None)
+            .map_err(|e| WorktreeError::GitOperation(e.to_string()))?;
+
+        let mut files = BTreeSet::new();
+        for item in status {
+            let item = item.map_err(|e| WorktreeError::GitOperation(e.to_string()))?;
+            if matches!(item, gix::status::Item::TreeIndex(_)) {
+                files.insert(item.location().to_string());
+            }
+        }
+        Ok(files)
+    }
+
+    /// List files changed by commits on this worktree since its merge base with `other`.
+    pub fn committed_files_since_base(&self, other: &Worktree) -> Result<BTreeSet<String>> {
+        let repo = open(self)?;
+        let other_repo = open(other)?;
+
+        let head = get_head_commit(&repo, &self.branch)?;
+        let other_head = get_head_commit(&other_repo, &other.branch)?;
+        let base_id = repo.merge_base(head, other_head.detach())?;
+
+        let base_tree = repo.find_tree(get_tree_id(&repo, base_id, "base")?)?;
+        let head_tree = repo.find_tree(get_tree_id(&repo, head, &self.branch)?)?;
+
+        let changes = repo
+            .diff_tree_to_tree(&base_tree, &head_tree, None)
+            .map_err(|e| WorktreeError::GitOperation(e.to_string()))?;
+
+        Ok(changes
+            .iter()
+            .filter(|c| !c.entry_mode().is_tree())
+            .map(|c| c.location().to_string())
+            .collect())
+    }
+}
+
+/// Open the repository for a worktree
+fn open(wt: &Worktree) -> Result<gix::Repository> {
+    gix::open(&wt.path).map_err(|_| WorktreeError::NotARepository {
+        path: wt.path.clone(),
+    })
+}
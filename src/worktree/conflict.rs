@@ -6,6 +6,10 @@
 
 use super::error::{Result, WorktreeError};
 use super::{Worktree, WorktreeManager};
+use crate::Suppressions;
+use gix::merge::tree::{Conflict, TreatAsUnresolved};
+use gix::prelude::ObjectIdExt;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Result of checking a pair of worktrees for conflicts
@@ -28,10 +32,89 @@ pub struct WorktreePairConflict {
 impl Worktree {
     /// Check for conflicts between this worktree and another
     pub fn conflicts_with(&self, other: &Worktree) -> Result<Vec<String>> {
-        // Open repository for the first worktree
-        let repo1 = gix::open(&self.path).map_err(|_| WorktreeError::NotARepository {
+        // Extract conflicting file paths
+        let conflicting_files: Vec<String> = self
+            .merge_conflicts(other, None)?
+            .into_iter()
+            .map(|conflict| conflict.ours.location().to_string())
+            .collect();
+
+        Ok(conflicting_files)
+    }
+
+    /// Check whether merging with `other` would leave `path` with a conflict
+    /// git cannot resolve on its own.
+    ///
+    /// With `content`, the file is first replaced on this worktree's HEAD and
+    /// the resulting hypothetical tree is merged instead, entirely in memory:
+    /// nothing is written to the object database. Unlike [`conflicts_with`],
+    /// files both sides changed but that merge cleanly don't count.
+    ///
+    /// [`conflicts_with`]: Worktree::conflicts_with
+    pub fn file_conflicts_with(
+        &self,
+        other: &Worktree,
+        path: &str,
+        content: Option<&[u8]>,
+    ) -> Result<bool> {
+        let conflicts = self.merge_conflicts(other, content.map(|c| (path, c)))?;
+        Ok(conflicts.iter().any(|c| {
+            c.is_unresolved(TreatAsUnresolved::git())
+                && (c.ours.location() == path || c.theirs.location() == path)
+        }))
+    }
+
+    /// Files that would conflict when merging two commits of this worktree's
+    /// repository, given as revisions (a commit id, branch or remote-tracking
+    /// branch).
+    ///
+    /// Like [`file_conflicts_with`](Worktree::file_conflicts_with), only
+    /// conflicts git cannot resolve on its own count.
+    pub fn revs_conflict(&self, ours: &str, theirs: &str) -> Result<Vec<String>> {
+        let repo = gix::open(&self.path).map_err(|_| WorktreeError::NotARepository {
             path: self.path.clone(),
         })?;
+        let resolve = |rev: &str| -> Result<gix::ObjectId> {
+            repo.rev_parse_single(rev)
+                .map_err(|e| e.to_string())
+                .and_then(|id| id.object().map_err(|e| e.to_string()))
+                .and_then(|object| object.peel_to_commit().map_err(|e| e.to_string()))
+                .map(|commit| commit.id)
+                .map_err(|reason| {
+                    WorktreeError::GitOperation(format!("cannot resolve '{}': {}", rev, reason))
+                })
+        };
+        let conflicts = merge_commits(
+            &repo,
+            resolve(ours)?,
+            resolve(theirs)?,
+            (ours, theirs),
+            None,
+        )?;
+        let mut files: Vec<String> = conflicts
+            .iter()
+            .filter(|c| c.is_unresolved(TreatAsUnresolved::git()))
+            .map(|c| c.ours.location().to_string())
+            .collect();
+        files.sort_unstable();
+        files.dedup();
+        Ok(files)
+    }
+
+    /// Merge this worktree's HEAD (optionally with one file replaced) with
+    /// `other`'s HEAD and return every conflict the merge ran into
+    fn merge_conflicts(
+        &self,
+        other: &Worktree,
+        edit: Option<(&str, &[u8])>,
+    ) -> Result<Vec<Conflict>> {
+        // Open repository for the first worktree, keeping any objects
+        // written for a hypothetical edit in memory
+        let repo1 = gix::open(&self.path)
+            .map_err(|_| WorktreeError::NotARepository {
+                path: self.path.clone(),
+            })?
+            .with_object_memory();
 
         // Open repository for the second worktree (or use the same if both are in same repo)
         let repo2 = gix::open(&other.path).map_err(|_| WorktreeError::NotARepository {
@@ -39,41 +122,86 @@ impl Worktree {
         })?;
 
         // Get the HEAD commits for each worktree
-        let head1 = get_head_commit(&repo1, &self.branch)?;
-        let head2 = get_head_commit(&repo2, &other.branch)?;
-
-        // Find merge base between the two commits
-        let base_id = repo1.merge_base(head1, head2)?;
-
-        // Get tree IDs for merge (not the tree objects themselves)
-        let base_tree_id = get_tree_id(&repo1, base_id, "base")?;
-        let tree1_id = get_tree_id(&repo1, head1, &self.branch)?;
-        let tree2_id = get_tree_id(&repo2, head2, &other.branch)?;
-
-        // Create labels for the merge
-        let labels = gix::merge::blob::builtin_driver::text::Labels {
-            ancestor: Some("base".into()),
-            current: Some(self.branch.as_str().into()),
-            other: Some(other.branch.as_str().into()),
-        };
+        let head1 = get_head_commit(&repo1, &self.branch)?.detach();
+        let head2 = get_head_commit(&repo2, &other.branch)?.detach();
 
-        // Get merge options
-        let options = repo1.tree_merge_options()?;
+        merge_commits(&repo1, head1, head2, (&self.branch, &other.branch), edit)
+    }
+}
 
-        // Perform the merge to detect conflicts
-        let merge_outcome = repo1
-            .merge_trees(base_tree_id, tree1_id, tree2_id, labels, options)
-            .map_err(|e| WorktreeError::MergeFailed(e.to_string()))?;
+/// Merge two commits (optionally with one file replaced on `ours`) and
+/// return every conflict the merge ran into.
+///
+/// Worktrees share one object database, so `repo` can be any worktree's.
+fn merge_commits(
+    repo: &gix::Repository,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+    labels: (&str, &str),
+    edit: Option<(&str, &[u8])>,
+) -> Result<Vec<Conflict>> {
+    Ok(merge_commit_trees(repo, ours, theirs, labels, edit)?.conflicts)
+}
 
-        // Extract conflicting file paths
-        let conflicting_files: Vec<String> = merge_outcome
-            .conflicts
-            .into_iter()
-            .map(|conflict| conflict.ours.location().to_string())
-            .collect();
+/// Merge the trees of two commits against their merge base; the merged
+/// tree is left unwritten in the outcome
+fn merge_commit_trees<'a>(
+    repo: &'a gix::Repository,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+    (ours_label, theirs_label): (&str, &str),
+    edit: Option<(&str, &[u8])>,
+) -> Result<gix::merge::tree::Outcome<'a>> {
+    // Find merge base between the two commits
+    let base_id = repo.merge_base(ours, theirs)?;
 
-        Ok(conflicting_files)
+    // Get tree IDs for merge (not the tree objects themselves)
+    let base_tree_id = get_tree_id(repo, base_id, "base")?;
+    let mut ours_tree_id = get_tree_id(repo, ours.attach(repo), ours_label)?;
+    let theirs_tree_id = get_tree_id(repo, theirs.attach(repo), theirs_label)?;
+
+    if let Some((path, content)) = edit {
+        ours_tree_id = edit_tree(repo, ours_tree_id, path, content)?;
     }
+
+    // Create labels for the merge
+    let labels = gix::merge::blob::builtin_driver::text::Labels {
+        ancestor: Some("base".into()),
+        current: Some(ours_label.into()),
+        other: Some(theirs_label.into()),
+    };
+
+    // Get merge options
+    let options = repo.tree_merge_options()?;
+
+    // Perform the merge to detect conflicts
+    repo.merge_trees(base_tree_id, ours_tree_id, theirs_tree_id, labels, options)
+        .map_err(|e| WorktreeError::MergeFailed(e.to_string()))
+}
+
+/// Write a copy of `tree_id` with `path` set to `content`, keeping the
+/// file's mode if it already exists
+fn edit_tree<'a>(
+    repo: &'a gix::Repository,
+    tree_id: gix::Id<'a>,
+    path: &str,
+    content: &[u8],
+) -> Result<gix::Id<'a>> {
+    let git_error = |e: &dyn std::fmt::Display| WorktreeError::GitOperation(e.to_string());
+
+    let kind = repo
+        .find_tree(tree_id)?
+        .peel_to_entry_by_path(path)
+        .map_err(|e| git_error(&e))?
+        .map(|entry| entry.mode().kind())
+        .unwrap_or(gix::object::tree::EntryKind::Blob);
+    let blob_id = repo.write_blob(content).map_err(|e| git_error(&e))?;
+
+    let mut editor = repo.edit_tree(tree_id).map_err(|e| git_error(&e))?;
+    editor
+        .upsert(path, kind, blob_id)
+        .map_err(|e| git_error(&e))?;
+    editor.write().map_err(|e| git_error(&e))
 }
 
 // ============================================================================
@@ -103,14 +231,105 @@ impl WorktreeManager {
         }
         results
     }
+
+    /// Merge the HEADs of `batch` one after another, the way a merge queue
+    /// lands them, and report the first merge git couldn't complete.
+    ///
+    /// Each step merges the next HEAD into the result of the previous ones,
+    /// entirely in memory: the intermediate trees and merge commits are
+    /// never written to the object database. Only conflicts git cannot
+    /// resolve on its own count, and `suppressions` hide a file's conflict
+    /// when it's ignored or acked between the merging worktree and one
+    /// already merged. Returns `None` if every merge is clean.
+    pub fn merge_in_sequence(
+        &self,
+        batch: &[&Worktree],
+        suppressions: &Suppressions,
+    ) -> Result<Option<SequenceConflict>> {
+        let Some(first) = batch.first() else {
+            return Ok(None);
+        };
+        let open = |wt: &Worktree| {
+            gix::open(&wt.path).map_err(|_| WorktreeError::NotARepository {
+                path: wt.path.clone(),
+            })
+        };
+        let repo = open(first)?.with_object_memory();
+        let mut merged = get_head_commit(&repo, &first.branch)?.detach();
+        let mut label = first.branch.clone();
+
+        for (step, wt) in batch.iter().enumerate().skip(1) {
+            let head = get_head_commit(&open(wt)?, &wt.branch)?.detach();
+            let mut outcome = merge_commit_trees(&repo, merged, head, (&label, &wt.branch), None)?;
+
+            let mut files: Vec<String> = outcome
+                .conflicts
+                .iter()
+                .filter(|c| c.is_unresolved(TreatAsUnresolved::git()))
+                .map(|c| c.ours.location().to_string())
+                .filter(|f| {
+                    !batch[..step]
+                        .iter()
+                        .any(|merged| suppressions.is_suppressed(merged, wt, f))
+                })
+                .collect();
+            if !files.is_empty() {
+                files.sort_unstable();
+                files.dedup();
+                return Ok(Some(SequenceConflict {
+                    merged: batch[..step].iter().map(|w| w.id.clone()).collect(),
+                    worktree: wt.id.clone(),
+                    files,
+                }));
+            }
+
+            // A merge commit, so the next step finds the right merge base
+            let git_error = |e: &dyn std::fmt::Display| WorktreeError::GitOperation(e.to_string());
+            let tree = outcome.tree.write().map_err(|e| git_error(&e))?;
+            let signature = gix::actor::Signature {
+                name: "clash".into(),
+                email: "clash@localhost".into(),
+                time: gix::date::Time::now_utc(),
+            };
+            let commit = gix::objs::Commit {
+                tree: tree.detach(),
+                parents: [merged, head].into_iter().collect(),
+                author: signature.clone(),
+                committer: signature,
+                encoding: None,
+                message: format!("Merge {} into {}", wt.branch, label).into(),
+                extra_headers: Vec::new(),
+            };
+            merged = repo
+                .write_object(commit)
+                .map_err(|e| git_error(&e))?
+                .detach();
+            label = format!("{}+{}", label, wt.branch);
+        }
+        Ok(None)
+    }
+}
+
+/// The step of a sequential merge that failed
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SequenceConflict {
+    /// Ids of the worktrees already merged before the failing step
+    pub merged: Vec<String>,
+    /// Id of the worktree whose merge failed
+    pub worktree: String,
+    /// Files git couldn't merge
+    pub files: Vec<String>,
 }
 
 // ============================================================================
-// Helper functions (private to this module)
+// Helper functions (shared with sibling modules)
 // ============================================================================
 
 /// Get HEAD commit ID for a worktree
-fn get_head_commit<'a>(repo: &'a gix::Repository, branch_name: &str) -> Result<gix::Id<'a>> {
+pub(super) fn get_head_commit<'a>(
+    repo: &'a gix::Repository,
+    branch_name: &str,
+) -> Result<gix::Id<'a>> {
     let mut head = repo.head().map_err(|e| WorktreeError::HeadResolution {
         branch: branch_name.to_string(),
         reason: e.to_string(),
@@ -129,7 +348,7 @@ fn get_head_commit<'a>(repo: &'a gix::Repository, branch_name: &str) -> Result<g
 }
 
 /// Get tree ID from a commit ID
-fn get_tree_id<'a>(
+pub(super) fn get_tree_id<'a>(
     repo: &'a gix::Repository,
     commit_id: gix::Id<'a>,
     label: &str,
@@ -0,0 +1,105 @@
+//! File content access for Worktree
+//!
+//! This module extends Worktree with helpers for reading individual files
+//! and the commit as they exist at HEAD, following the pattern of splitting
+//! impl blocks across files by functionality.
+
+use super::Worktree;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Summary of a commit for display
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CommitInfo {
+    /// Full commit id
+    pub id: String,
+    /// First line of the commit message
+    pub summary: String,
+    pub author: String,
+    /// RFC 3339 commit time
+    pub time: String,
+}
+
+impl Worktree {
+    /// Get the blob id of a file at this worktree's HEAD.
+    ///
+    /// Returns `None` if the repository can't be opened, HEAD is unborn,
+    /// or the file is not tracked at HEAD.
+    pub fn head_blob_id(&self, file_path: &str) -> Option<String> {
+        let repo = gix::open(&self.path).ok()?;
+        let mut tree = head_tree(&repo)?;
+        let entry = tree.peel_to_entry_by_path(file_path).ok()??;
+        Some(entry.id().to_string())
+    }
+
+    /// Get the commit this worktree's HEAD points at.
+    ///
+    /// Returns `None` if the repository can't be opened or HEAD is unborn.
+    pub fn head_commit(&self) -> Option<CommitInfo> {
+        let repo = gix::open(&self.path).ok()?;
+        commit_info(&repo.head_commit().ok()?)
+    }
+
+    /// Get up to `limit` commits reachable from HEAD, newest first.
+    ///
+    /// Returns an empty list if the repository can't be opened or HEAD is unborn.
+    pub fn recent_commits(&self, limit: usize) -> Vec<CommitInfo> {
+        let Some(repo) = gix::open(&self.path).ok() else {
+            return Vec::new();
+        };
+        let Some(head) = repo.head_id().ok() else {
+            return Vec::new();
+        };
+        let Ok(walk) = repo.rev_walk([head]).all() else {
+            return Vec::new();
+        };
+        walk.filter_map(|info| info.ok()?.object().ok())
+            .filter_map(|commit| commit_info(&commit))
+            .take(limit)
+            .collect()
+    }
+
+    /// Read a file's staged contents from this worktree's index.
+    ///
+    /// Returns `None` if the file isn't in the index (e.g. a staged deletion).
+    pub fn staged_file_contents(&self, file_path: &str) -> Option<Vec<u8>> {
+        let repo = gix::open(&self.path).ok()?;
+        let index = repo.index_or_empty().ok()?;
+        let entry = index.entry_by_path(file_path.into())?;
+        Some(repo.find_blob(entry.id).ok()?.data.clone())
+    }
+
+    /// Read a file's contents from this worktree's HEAD.
+    pub fn head_file_contents(&self, file_path: &str) -> Option<Vec<u8>> {
+        let repo = gix::open(&self.path).ok()?;
+        head_file_contents(&repo, file_path)
+    }
+}
+
+/// Summarize a commit for display
+fn commit_info(commit: &gix::Commit<'_>) -> Option<CommitInfo> {
+    let author = commit.author().ok()?;
+    let time = commit.time().ok()?;
+    Some(CommitInfo {
+        id: commit.id.to_string(),
+        summary: commit.message().ok()?.summary().to_string(),
+        author: author.name.to_string(),
+        time: chrono::DateTime::from_timestamp(time.seconds, 0)?.to_rfc3339(),
+    })
+}
+
+/// Read a file's contents from HEAD in the given repository.
+fn head_file_contents(repo: &gix::Repository, file_path: &str) -> Option<Vec<u8>> {
+    let mut tree = head_tree(repo)?;
+    let entry = tree.peel_to_entry_by_path(file_path).ok()??;
+    let blob = repo.find_object(entry.id()).ok()?;
+    Some(blob.data.to_vec())
+}
+
+/// Resolve the tree of HEAD in the given repository.
+fn head_tree(repo: &gix::Repository) -> Option<gix::Tree<'_>> {
+    let mut head = repo.head().ok()?;
+    let head_id = head.try_peel_to_id().ok()??;
+    let commit = repo.find_object(head_id).ok()?.try_into_commit().ok()?;
+    commit.tree().ok()
+}
@@ -74,3 +74,9 @@ impl From<gix::object::find::existing::Error> for WorktreeError {
         WorktreeError::GitOperation(err.to_string())
     }
 }
+
+impl From<gix::object::find::existing::with_conversion::Error> for WorktreeError {
+    fn from(err: gix::object::find::existing::with_conversion::Error) -> Self {
+        WorktreeError::GitOperation(err.to_string())
+    }
+}
@@ -0,0 +1,285 @@
+//! Line-level diffs of a file between two worktrees
+//!
+//! This module extends Worktree with methods that diff one file on both
+//! sides of a pair against their merge base. The resulting hunks are in
+//! merge-base line numbers, so hunks from the two sides can be compared to
+//! find the regions where they collide.
+
+use super::Worktree;
+use super::conflict::{get_head_commit, get_tree_id};
+use super::error::{Result, WorktreeError};
+use imara_diff::intern::InternedInput;
+use imara_diff::{Algorithm, diff};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Which version of a worktree's file to diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The committed version at HEAD (what merge conflicts are computed on)
+    Head,
+    /// The file on disk, including uncommitted edits
+    Workdir,
+}
+
+/// A changed region between two versions of a file.
+///
+/// Line numbers are 1-based, as in unified diffs. For pure insertions
+/// `old_lines` is 0 and `old_start` is the line the insertion precedes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Hunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    /// Lines removed from the old version
+    pub removed: Vec<String>,
+    /// Lines added in the new version
+    pub added: Vec<String>,
+}
+
+impl Hunk {
+    /// Check whether two hunks against the same old version collide.
+    ///
+    /// Hunks collide when their old ranges overlap or lie within `slack`
+    /// lines of each other. With zero slack, adjacent hunks still collide,
+    /// matching git's merge behaviour.
+    pub fn collides_with(&self, other: &Hunk, slack: u32) -> bool {
+        let (a0, a1) = (self.old_start, self.old_start + self.old_lines);
+        let (b0, b1) = (other.old_start, other.old_start + other.old_lines);
+        a0 <= b1 + slack && b0 <= a1 + slack
+    }
+}
+
+/// Diff two texts line by line
+pub fn diff_lines(old: &str, new: &str) -> Vec<Hunk> {
+    let input = InternedInput::new(old, new);
+    let mut hunks = Vec::new();
+    diff(
+        Algorithm::Histogram,
+        &input,
+        |before: std::ops::Range<u32>, after: std::ops::Range<u32>| {
+            let line = |t: u32, side: &[imara_diff::intern::Token]| {
+                input.interner[side[t as usize]]
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string()
+            };
+            hunks.push(Hunk {
+                old_start: before.start + 1,
+                old_lines: before.end - before.start,
+                new_start: after.start + 1,
+                new_lines: after.end - after.start,
+                removed: before.clone().map(|t| line(t, &input.before)).collect(),
+                added: after.clone().map(|t| line(t, &input.after)).collect(),
+            });
+        },
+    );
+    hunks
+}
+
+/// How a file diverged on the two sides of a pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// Both sides edited the content of an existing file
+    Content,
+    /// Both sides added a file that didn't exist at the merge base
+    AddAdd,
+    /// One side modified the file and the other deleted it
+    ModifyDelete,
+    /// At most one side changed the file
+    None,
+}
+
+impl std::fmt::Display for DivergenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DivergenceKind::Content => write!(f, "content"),
+            DivergenceKind::AddAdd => write!(f, "add/add"),
+            DivergenceKind::ModifyDelete => write!(f, "modify/delete"),
+            DivergenceKind::None => write!(f, "none"),
+        }
+    }
+}
+
+/// A region where both sides changed overlapping merge-base lines
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConflictRegion {
+    /// First merge-base line covered by the region (1-based)
+    pub base_start: u32,
+    /// Number of merge-base lines covered by the region
+    pub base_lines: u32,
+    /// This side's hunk
+    pub ours: Hunk,
+    /// The other side's hunk
+    pub theirs: Hunk,
+}
+
+/// One file diffed on both sides of a worktree pair against their merge base
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairFileDiff {
+    pub path: String,
+    /// Merge base commit id
+    pub base_commit: String,
+    /// File text at the merge base (`None` if absent)
+    pub base: Option<String>,
+    /// File text on this side (`None` if absent)
+    pub ours: Option<String>,
+    /// File text on the other side (`None` if absent)
+    pub theirs: Option<String>,
+    /// This side's changes against the merge base
+    pub ours_hunks: Vec<Hunk>,
+    /// The other side's changes against the merge base
+    pub theirs_hunks: Vec<Hunk>,
+}
+
+impl PairFileDiff {
+    /// Classify how the two sides diverged
+    pub fn kind(&self) -> DivergenceKind {
+        let ours_changed = self.ours != self.base;
+        let theirs_changed = self.theirs != self.base;
+        if !ours_changed || !theirs_changed || self.ours == self.theirs {
+            return DivergenceKind::None;
+        }
+        match (&self.base, &self.ours, &self.theirs) {
+            (None, Some(_), Some(_)) => DivergenceKind::AddAdd,
+            (Some(_), None, _) | (Some(_), _, None) => DivergenceKind::ModifyDelete,
+            _ => DivergenceKind::Content,
+        }
+    }
+
+    /// Map a 1-based merge-base line to this side's version of the file,
+    /// shifting it by the lines our own hunks added or removed above it
+    pub fn map_base_line(&self, base_line: u32) -> u32 {
+        let shift: i64 = self
+            .ours_hunks
+            .iter()
+            .filter(|h| h.old_start + h.old_lines <= base_line)
+            .map(|h| i64::from(h.new_lines) - i64::from(h.old_lines))
+            .sum();
+        (i64::from(base_line) + shift).max(1) as u32
+    }
+
+    /// Find regions where both sides' hunks collide (see [`Hunk::collides_with`])
+    pub fn regions(&self, slack: u32) -> Vec<ConflictRegion> {
+        let mut regions = Vec::new();
+        for ours in &self.ours_hunks {
+            for theirs in &self.theirs_hunks {
+                if ours.collides_with(theirs, slack) {
+                    let start = ours.old_start.min(theirs.old_start);
+                    let end =
+                        (ours.old_start + ours.old_lines).max(theirs.old_start + theirs.old_lines);
+                    regions.push(ConflictRegion {
+                        base_start: start,
+                        base_lines: end - start,
+                        ours: ours.clone(),
+                        theirs: theirs.clone(),
+                    });
+                }
+            }
+        }
+        regions
+    }
+}
+
+impl Worktree {
+    /// Diff `path` on this worktree and `other` against their merge base.
+    ///
+    /// `side` selects whether each worktree's committed or on-disk version is used.
+    pub fn diff_file_against(
+        &self,
+        other: &Worktree,
+        path: &str,
+        side: Side,
+    ) -> Result<PairFileDiff> {
+        let repo = gix::open(&self.path).map_err(|_| WorktreeError::NotARepository {
+            path: self.path.clone(),
+        })?;
+        let other_repo = gix::open(&other.path).map_err(|_| WorktreeError::NotARepository {
+            path: other.path.clone(),
+        })?;
+
+        let head = get_head_commit(&repo, &self.branch)?;
+        let other_head = get_head_commit(&other_repo, &other.branch)?;
+        let base_id = repo.merge_base(head, other_head.detach())?;
+
+        let base = tree_file_text(&repo, get_tree_id(&repo, base_id, "base")?, path)?;
+        let ours = match side {
+            Side::Head => tree_file_text(&repo, get_tree_id(&repo, head, &self.branch)?, path)?,
+            Side::Workdir => disk_file_text(self, path),
+        };
+        let theirs = match side {
+            Side::Head => tree_file_text(
+                &other_repo,
+                get_tree_id(&other_repo, other_head, &other.branch)?,
+                path,
+            )?,
+            Side::Workdir => disk_file_text(other, path),
+        };
+
+        let base_text = base.as_deref().unwrap_or("");
+        Ok(PairFileDiff {
+            path: path.to_string(),
+            base_commit: base_id.to_string(),
+            ours_hunks: diff_lines(base_text, ours.as_deref().unwrap_or("")),
+            theirs_hunks: diff_lines(base_text, theirs.as_deref().unwrap_or("")),
+            base,
+            ours,
+            theirs,
+        })
+    }
+}
+
+/// Read a file's text from a tree, `None` if the path doesn't exist there
+fn tree_file_text(
+    repo: &gix::Repository,
+    tree_id: gix::Id<'_>,
+    path: &str,
+) -> Result<Option<String>> {
+    let mut tree = repo.find_tree(tree_id)?;
+    let entry = tree
+        .peel_to_entry_by_path(path)
+        .map_err(|e| WorktreeError::GitOperation(e.to_string()))?;
+    Ok(match entry {
+        Some(entry) => {
+            let blob = repo.find_object(entry.id())?;
+            Some(String::from_utf8_lossy(&blob.data).into_owned())
+        }
+        None => None,
+    })
+}
+
+/// Read a file's text from a worktree's working directory
+fn disk_file_text(wt: &Worktree, path: &str) -> Option<String> {
+    std::fs::read(wt.path.join(path))
+        .ok()
+        .map(|data| String::from_utf8_lossy(&data).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_reports_one_based_hunks() {
+        let hunks = diff_lines("a\nb\nc\n", "a\nB\nc\nd\n");
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].old_lines), (2, 1));
+        assert_eq!(hunks[0].removed, vec!["b"]);
+        assert_eq!(hunks[0].added, vec!["B"]);
+        assert_eq!((hunks[1].old_start, hunks[1].old_lines), (4, 0));
+        assert_eq!(hunks[1].added, vec!["d"]);
+    }
+
+    #[test]
+    fn adjacent_hunks_collide_but_distant_ones_need_slack() {
+        let base = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let ours = &diff_lines(base, "1\nX\n3\n4\n5\n6\n7\n8\n")[0];
+        let adjacent = &diff_lines(base, "1\n2\nY\n4\n5\n6\n7\n8\n")[0];
+        let distant = &diff_lines(base, "1\n2\n3\n4\n5\n6\nZ\n8\n")[0];
+
+        assert!(ours.collides_with(adjacent, 0));
+        assert!(!ours.collides_with(distant, 0));
+        assert!(ours.collides_with(distant, 4));
+    }
+}
@@ -4,13 +4,14 @@ use super::error::{Result, WorktreeError};
 use super::{
     DETACHED_HEAD_LABEL, INACCESSIBLE_PATH_LABEL, MAIN_WORKTREE_ID, Worktree, WorktreeStatus,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Manager for all worktrees in a git repository
 #[derive(Debug, Clone)]
 pub struct WorktreeManager {
     items: Vec<Worktree>,
     repo_path: PathBuf,
+    common_dir: PathBuf,
 }
 
 impl WorktreeManager {
@@ -25,27 +26,7 @@ impl WorktreeManager {
     /// relative paths against cwd, then uses `gix::discover` to walk
     /// up and find the containing git repository.
     pub fn discover_from(path: &str) -> Result<Self> {
-        let input = PathBuf::from(path);
-        let abs_path = if input.is_absolute() {
-            input
-        } else {
-            std::env::current_dir()
-                .and_then(|d| d.canonicalize().or(Ok(d)))
-                .unwrap_or_else(|_| input.clone())
-                .join(&input)
-        };
-
-        // gix::discover expects a directory; if the path isn't a directory
-        // (file or non-existent path for new files), use its parent
-        let discover_path = if abs_path.is_dir() {
-            abs_path.clone()
-        } else {
-            abs_path.parent().unwrap_or(&abs_path).to_path_buf()
-        };
-
-        let repo = gix::discover(&discover_path).map_err(|_| WorktreeError::NotARepository {
-            path: abs_path.clone(),
-        })?;
+        let repo = discover_repo(path)?;
 
         let mut items = Vec::new();
 
@@ -53,13 +34,7 @@ impl WorktreeManager {
         // repo.workdir() returns the cwd worktree (wrong if discovered from
         // a linked worktree). The common_dir is the shared .git/ directory
         // whose parent is always the main worktree root.
-        //
-        // gix may return common_dir with relative components
-        // (e.g. `.git/worktrees/name/../..`), so canonicalize first.
-        let common_dir = repo
-            .common_dir()
-            .canonicalize()
-            .unwrap_or_else(|_| repo.common_dir().to_path_buf());
+        let common_dir = canonical_common_dir(&repo);
         let main_path = common_dir
             .parent()
             .and_then(|p| p.canonicalize().ok())
@@ -136,9 +111,18 @@ impl WorktreeManager {
         Ok(Self {
             items,
             repo_path: PathBuf::from(path),
+            common_dir,
         })
     }
 
+    /// Find the common git directory of the repository containing `path`.
+    ///
+    /// Much cheaper than [`discover_from`](Self::discover_from) since it
+    /// doesn't open every worktree to read its branch and status.
+    pub fn discover_common_dir(path: &str) -> Result<PathBuf> {
+        Ok(canonical_common_dir(&discover_repo(path)?))
+    }
+
     /// Refresh worktree information by re-discovering
     pub fn refresh(&mut self) -> Result<()> {
         // Convert path to string, handling non-UTF-8 paths
@@ -163,6 +147,24 @@ impl WorktreeManager {
         self.items.iter().find(|w| w.id == MAIN_WORKTREE_ID)
     }
 
+    /// Get the common `.git` directory shared by all worktrees
+    pub fn common_dir(&self) -> &Path {
+        &self.common_dir
+    }
+
+    /// Find a worktree by id, falling back to branch name.
+    ///
+    /// Ids are unique, so an id match always wins over a branch match.
+    pub fn find(&self, name: &str) -> Result<&Worktree> {
+        self.items
+            .iter()
+            .find(|w| w.id == name)
+            .or_else(|| self.items.iter().find(|w| w.branch == name))
+            .ok_or_else(|| WorktreeError::BranchNotFound {
+                branch: name.to_string(),
+            })
+    }
+
     /// Get the number of worktrees
     pub fn len(&self) -> usize {
         self.items.len()
@@ -192,7 +194,50 @@ impl WorktreeManager {
         }
         None
     }
+
+    /// Find the worktree containing the current working directory
+    pub fn current(&self) -> Option<&Worktree> {
+        let cwd = std::env::current_dir().ok()?;
+        let cwd = cwd.canonicalize().unwrap_or(cwd);
+        self.find_containing(&cwd)
+    }
 }
 
 // WorktreeManager methods are extended in other modules:
 // - conflict.rs: adds check_all_conflicts(), check_conflicts_between(), etc.
+
+/// Open the repository containing `path` (a file, directory, or `.` for cwd).
+///
+/// Resolves relative paths against cwd, then uses `gix::discover` to walk
+/// up and find the containing git repository.
+fn discover_repo(path: &str) -> Result<gix::Repository> {
+    let input = PathBuf::from(path);
+    let abs_path = if input.is_absolute() {
+        input
+    } else {
+        std::env::current_dir()
+            .and_then(|d| d.canonicalize().or(Ok(d)))
+            .unwrap_or_else(|_| input.clone())
+            .join(&input)
+    };
+
+    // gix::discover expects an existing directory; for a file, or a new
+    // file in directories that don't exist yet, use the nearest one above
+    let discover_path = abs_path
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(&abs_path)
+        .to_path_buf();
+
+    gix::discover(&discover_path).map_err(|_| WorktreeError::NotARepository { path: abs_path })
+}
+
+/// The shared `.git/` directory of a repository.
+///
+/// gix may return common_dir with relative components
+/// (e.g. `.git/worktrees/name/../..`), so canonicalize first.
+fn canonical_common_dir(repo: &gix::Repository) -> PathBuf {
+    repo.common_dir()
+        .canonicalize()
+        .unwrap_or_else(|_| repo.common_dir().to_path_buf())
+}